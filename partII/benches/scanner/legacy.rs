@@ -0,0 +1,390 @@
+//! The scanner as it was before it moved to a byte cursor, kept as a
+//! baseline. It indexes the source by `char`, so every step is linear in the
+//! size of the script, and it only accepts ASCII identifiers.
+
+use lox::error::{Error, ScannerError, ScannerErrors};
+use lox::token::{Span, Token, TokenType};
+
+type Result<T> = std::result::Result<T, ScannerError>;
+
+#[derive(Debug)]
+pub struct Scanner {
+    source: String,
+    tokens: Vec<Token>,
+
+    start: usize,
+    current: usize,
+    line: usize,
+    /// Index of the first character of the current line.
+    line_start: usize,
+
+    start_line: usize,
+    start_column: usize,
+
+    /// The `${` being scanned, innermost last, with the number of `{` opened
+    /// since then.
+    interpolations: Vec<(Span, usize)>,
+}
+
+impl Scanner {
+    pub fn new(source: String) -> Self {
+        Self {
+            source,
+            tokens: Vec::new(),
+            start: 0,
+            current: 0,
+            line: 1,
+            line_start: 0,
+            start_line: 1,
+            start_column: 1,
+            interpolations: Vec::new(),
+        }
+    }
+
+    pub fn scan_tokens(mut self) -> std::result::Result<Vec<Token>, Error> {
+        let mut errors = Vec::new();
+
+        while !self.is_at_end() {
+            self.start = self.current;
+            self.start_line = self.line;
+            self.start_column = self.current - self.line_start + 1;
+            if let Err(e) = self.scan_token() {
+                errors.push(e);
+            }
+        }
+
+        errors.extend(
+            self.interpolations
+                .drain(..)
+                .map(|(span, _)| ScannerError::Interpolation(span)),
+        );
+
+        self.start = self.current;
+        self.start_line = self.line;
+        self.start_column = self.current - self.line_start + 1;
+        self.add_token(TokenType::EoF);
+
+        if errors.is_empty() {
+            Ok(self.tokens)
+        } else {
+            Err(ScannerErrors(errors).into())
+        }
+    }
+
+    fn is_at_end(&self) -> bool {
+        self.current >= self.source.chars().count()
+    }
+
+    fn scan_token(&mut self) -> Result<()> {
+        let c = self.advance().unwrap();
+
+        match c {
+            '(' => self.add_token(TokenType::LeftParen),
+            ')' => self.add_token(TokenType::RightParen),
+            '{' => {
+                if let Some((_, depth)) = self.interpolations.last_mut() {
+                    *depth += 1;
+                }
+                self.add_token(TokenType::LeftBrace)
+            }
+            '}' => match self.interpolations.last_mut() {
+                // the end of an interpolated expression, back to the string
+                Some((_, 0)) => {
+                    self.interpolations.pop();
+                    self.string()?;
+                }
+                Some((_, depth)) => {
+                    *depth -= 1;
+                    self.add_token(TokenType::RightBrace)
+                }
+                None => self.add_token(TokenType::RightBrace),
+            },
+            '[' => self.add_token(TokenType::LeftBracket),
+            ']' => self.add_token(TokenType::RightBracket),
+            ':' => self.add_token(TokenType::Colon),
+            '?' => self.add_token(TokenType::Question),
+            ',' => self.add_token(TokenType::Comma),
+            '.' => self.add_token(TokenType::Dot),
+            ';' => self.add_token(TokenType::Semicolon),
+
+            '-' if self.follow('-') => self.add_token(TokenType::MinusMinus),
+            '-' if self.follow('=') => self.add_token(TokenType::MinusEqual),
+            '-' => self.add_token(TokenType::Minus),
+            '+' if self.follow('+') => self.add_token(TokenType::PlusPlus),
+            '+' if self.follow('=') => self.add_token(TokenType::PlusEqual),
+            '+' => self.add_token(TokenType::Plus),
+            '*' if self.follow('*') => self.add_token(TokenType::StarStar),
+            '*' if self.follow('=') => self.add_token(TokenType::StarEqual),
+            '*' => self.add_token(TokenType::Star),
+
+            '!' if self.follow('=') => self.add_token(TokenType::BangEqual),
+            '!' => self.add_token(TokenType::Bang),
+            '=' if self.follow('=') => self.add_token(TokenType::EqualEqual),
+            '=' => self.add_token(TokenType::Equal),
+            '<' if self.follow('=') => self.add_token(TokenType::LessEqual),
+            '<' => self.add_token(TokenType::Less),
+            '>' if self.follow('=') => self.add_token(TokenType::GreaterEqual),
+            '>' => self.add_token(TokenType::Greater),
+            // `///` is a doc comment, but `////` is a plain comment
+            '/' if self.follow('/') => {
+                if self.peek() == '/' && self.peek_next() != '/' {
+                    self.advance();
+                    self.doc_comment();
+                } else {
+                    while self.peek() != '\n' && !self.is_at_end() {
+                        self.advance();
+                    }
+                }
+            }
+            '/' if self.follow('*') => self.block_comment()?,
+            '/' if self.follow('=') => self.add_token(TokenType::SlashEqual),
+            '~' if self.follow('/') => self.add_token(TokenType::TildeSlash),
+            '%' => self.add_token(TokenType::Percent),
+            '/' => self.add_token(TokenType::Slash),
+            '\n' => self.new_line(),
+            c if c.is_whitespace() => (),
+
+            '"' if self.follow('"') => {
+                if self.follow('"') {
+                    self.raw_string()?
+                } else {
+                    self.add_token(TokenType::String(String::new()))
+                }
+            }
+            '"' => self.string()?,
+            c if c.is_ascii_digit() => self.number()?,
+            c if c.is_ascii_alphabetic() || c == '_' => self.identifier(),
+
+            c => return Err(ScannerError::Character(c, self.span())),
+        }
+
+        Ok(())
+    }
+
+    fn peek(&self) -> char {
+        self.current().unwrap_or('\0')
+    }
+
+    fn peek_next(&self) -> char {
+        self.source.chars().nth(self.current + 1).unwrap_or('\0')
+    }
+
+    fn follow(&mut self, c: char) -> bool {
+        if let Some(cur) = self.current() {
+            if c == cur {
+                self.advance();
+                return true;
+            }
+        }
+        false
+    }
+
+    fn advance(&mut self) -> Option<char> {
+        let c = self.source.chars().nth(self.current);
+        if c.is_some() {
+            self.current += 1;
+        }
+        c
+    }
+
+    fn current(&self) -> Option<char> {
+        self.source.chars().nth(self.current)
+    }
+
+    fn new_line(&mut self) {
+        self.line += 1;
+        self.line_start = self.current;
+    }
+
+    /// The span of the token currently being scanned.
+    fn span(&self) -> Span {
+        Span {
+            offset: self.start,
+            len: self.current - self.start,
+            line: self.start_line,
+            column: self.start_column,
+        }
+    }
+
+    fn add_token(&mut self, token_type: TokenType) {
+        let text = &self.source[self.start..self.current];
+        self.tokens.push(Token {
+            ty: token_type,
+            lexeme: text.to_string(),
+            span: self.span(),
+        });
+    }
+
+    /// Scan the rest of a string, after its opening `"` or after the `}` of
+    /// an interpolation, up to its closing `"` or the next `${`.
+    fn string(&mut self) -> Result<()> {
+        let mut value = String::new();
+        // keep scanning after a bad escape so the rest of the string isn't
+        // reported as code
+        let mut error = None;
+
+        loop {
+            match self.advance() {
+                None => return Err(ScannerError::String(self.span())),
+                Some('"') => break,
+                Some('$') if self.follow('{') => {
+                    let span = Span {
+                        offset: self.current - 2,
+                        len: 2,
+                        line: self.line,
+                        column: self.current - 2 - self.line_start + 1,
+                    };
+                    self.interpolations.push((span, 0));
+                    self.add_token(TokenType::Interpolation(value));
+                    return error.map_or(Ok(()), Err);
+                }
+                Some('\\') => match self.escape() {
+                    Ok(c) => value.push(c),
+                    Err(e) => {
+                        error.get_or_insert(e);
+                    }
+                },
+                Some('\n') => {
+                    self.new_line();
+                    value.push('\n');
+                }
+                Some(c) => value.push(c),
+            }
+        }
+
+        self.add_token(TokenType::String(value));
+        error.map_or(Ok(()), Err)
+    }
+
+    /// Decode the escape sequence following a `\\`.
+    fn escape(&mut self) -> Result<char> {
+        let start = self.current - 1;
+        let span = |scanner: &Self| Span {
+            offset: start,
+            len: scanner.current - start,
+            line: scanner.line,
+            column: start - scanner.line_start + 1,
+        };
+
+        let c = match self.advance() {
+            Some('n') => '\n',
+            Some('t') => '\t',
+            Some('r') => '\r',
+            Some('0') => '\0',
+            Some('\\') => '\\',
+            Some('"') => '"',
+            Some('$') => '$',
+            Some('u') => {
+                let mut digits = String::new();
+                let closed = self.follow('{') && {
+                    while self.peek().is_ascii_hexdigit() && digits.len() < 6 {
+                        digits.extend(self.advance());
+                    }
+                    self.follow('}')
+                };
+                let c = u32::from_str_radix(&digits, 16)
+                    .ok()
+                    .and_then(char::from_u32)
+                    .filter(|_| closed);
+                let text = self.source[start..self.current].to_string();
+                return c.ok_or_else(|| ScannerError::UnicodeEscape(text, span(self)));
+            }
+            Some('\n') => {
+                let error = ScannerError::Escape(String::from("\\"), span(self));
+                self.new_line();
+                return Err(error);
+            }
+            Some(c) => return Err(ScannerError::Escape(format!("\\{c}"), span(self))),
+            // the string itself is reported as unterminated
+            None => return Err(ScannerError::Escape(String::from("\\"), span(self))),
+        };
+        Ok(c)
+    }
+
+    fn doc_comment(&mut self) {
+        let mut text = String::new();
+        while self.peek() != '\n' && !self.is_at_end() {
+            text.extend(self.advance());
+        }
+
+        let text = text.strip_prefix(' ').unwrap_or(&text).trim_end();
+        self.add_token(TokenType::DocComment(text.to_string()));
+    }
+
+    /// Skip a `/* */` comment, which can contain other block comments.
+    fn block_comment(&mut self) -> Result<()> {
+        let mut depth = 1;
+
+        while depth > 0 {
+            match self.advance() {
+                None => return Err(ScannerError::Comment(self.span())),
+                Some('/') if self.follow('*') => depth += 1,
+                Some('*') if self.follow('/') => depth -= 1,
+                Some('\n') => self.new_line(),
+                Some(_) => (),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Scan a `"""` string, taken verbatim up to the closing `"""`.
+    fn raw_string(&mut self) -> Result<()> {
+        let mut value = String::new();
+
+        loop {
+            match self.advance() {
+                None => return Err(ScannerError::String(self.span())),
+                Some('"') if self.peek() == '"' && self.peek_next() == '"' => {
+                    self.advance();
+                    self.advance();
+                    break;
+                }
+                Some('\n') => {
+                    self.new_line();
+                    value.push('\n');
+                }
+                Some(c) => value.push(c),
+            }
+        }
+
+        self.add_token(TokenType::String(value));
+        Ok(())
+    }
+
+    fn number(&mut self) -> Result<()> {
+        while self.peek().is_ascii_digit() {
+            self.advance();
+        }
+
+        if self.peek() == '.' && self.peek_next().is_ascii_digit() {
+            self.advance();
+
+            while self.peek().is_ascii_digit() {
+                self.advance();
+            }
+        }
+
+        let number = &self.source[self.start..self.current];
+        let number = number
+            .parse()
+            .map_err(|e| ScannerError::Number(number.to_string(), e, self.span()))?;
+        self.add_token(TokenType::Number(number));
+
+        Ok(())
+    }
+
+    fn identifier(&mut self) {
+        while self.peek().is_ascii_alphanumeric() {
+            self.advance();
+        }
+
+        let ident = self.source[self.start..self.current].to_string();
+
+        if let Some(keyword) = TokenType::from_keyword(&ident) {
+            self.add_token(keyword);
+        } else {
+            self.add_token(TokenType::Identifier(ident));
+        }
+    }
+}
@@ -0,0 +1,57 @@
+//! Compare the scanner with the char-indexed one it replaced, on scripts made
+//! of the code samples repeated up to a given number of lines.
+//!
+//! ```text
+//! cargo bench --bench scanner
+//! ```
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+mod legacy;
+
+/// Build a script of at least `lines` lines from the ASCII code samples, which
+/// the legacy scanner can handle.
+fn script(lines: usize) -> String {
+    let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/code_samples");
+    let mut samples: Vec<_> = std::fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| std::fs::read_to_string(entry.unwrap().path()).unwrap())
+        .filter(|sample| sample.is_ascii())
+        .collect();
+    samples.sort();
+
+    let mut script = String::new();
+    for sample in samples.iter().cycle() {
+        if script.lines().count() >= lines {
+            break;
+        }
+        script.push_str(sample);
+        script.push('\n');
+    }
+    script
+}
+
+fn scanner(c: &mut Criterion) {
+    let mut group = c.benchmark_group("scanner");
+    group.sample_size(10);
+
+    for lines in [100, 1_000, 10_000] {
+        let source = script(lines);
+        group.throughput(Throughput::Bytes(source.len() as u64));
+
+        group.bench_with_input(BenchmarkId::new("cursor", lines), &source, |b, source| {
+            b.iter(|| lox::scanner::Scanner::new(source.clone()).scan_tokens())
+        });
+        // quadratic, 10k lines would take minutes
+        if lines <= 1_000 {
+            group.bench_with_input(BenchmarkId::new("legacy", lines), &source, |b, source| {
+                b.iter(|| legacy::Scanner::new(source.clone()).scan_tokens())
+            });
+        }
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, scanner);
+criterion_main!(benches);
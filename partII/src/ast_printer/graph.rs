@@ -5,7 +5,7 @@ impl Expr {
         let mut res = String::new();
         res.push_str("digraph G {\n\t");
         res.push_str(&self._graph(&mut 0));
-        res.push_str("}");
+        res.push('}');
 
         res
     }
@@ -26,6 +26,20 @@ impl Expr {
                 res.push_str(&format!("\t{id} -> {}", left._graph(count)));
                 res.push_str(&format!("\t{id} -> {}", right._graph(count)));
             }
+            Self::Conditional {
+                condition,
+                then_branch,
+                else_branch,
+                ..
+            } => {
+                let id = format!("conditional_{count}");
+                *count += 1;
+                res.push_str(&format!("{id}\n"));
+                res.push_str(&format!("\t{id} [label=\"?:\"]\n"));
+                res.push_str(&format!("\t{id} -> {}", condition._graph(count)));
+                res.push_str(&format!("\t{id} -> {}", then_branch._graph(count)));
+                res.push_str(&format!("\t{id} -> {}", else_branch._graph(count)));
+            }
             Self::Grouping { expression } => {
                 let id = format!("group_{count}");
                 *count += 1;
@@ -46,6 +60,71 @@ impl Expr {
                 res.push_str(&format!("\t{id} [label=\"{}\"]\n", operator.lexeme));
                 res.push_str(&format!("\t{id} -> {}", right._graph(count)));
             }
+            Self::List { elements, .. } => {
+                let id = format!("list_{count}");
+                *count += 1;
+                res.push_str(&format!("{id}\n"));
+                res.push_str(&format!("\t{id} [label=\"list\"]\n"));
+                for element in elements {
+                    res.push_str(&format!("\t{id} -> {}", element._graph(count)));
+                }
+            }
+            Self::Map { entries, .. } => {
+                let id = format!("map_{count}");
+                *count += 1;
+                res.push_str(&format!("{id}\n"));
+                res.push_str(&format!("\t{id} [label=\"map\"]\n"));
+                for (key, value) in entries {
+                    let entry = format!("entry_{count}");
+                    *count += 1;
+                    res.push_str(&format!("\t{id} -> {entry}\n"));
+                    res.push_str(&format!("\t{entry} [label=\":\"]\n"));
+                    res.push_str(&format!("\t{entry} -> {}", key._graph(count)));
+                    res.push_str(&format!("\t{entry} -> {}", value._graph(count)));
+                }
+            }
+            Self::Index { object, index, .. } => {
+                let id = format!("index_{count}");
+                *count += 1;
+                res.push_str(&format!("{id}\n"));
+                res.push_str(&format!("\t{id} [label=\"[]\"]\n"));
+                res.push_str(&format!("\t{id} -> {}", object._graph(count)));
+                res.push_str(&format!("\t{id} -> {}", index._graph(count)));
+            }
+            Self::SetIndex {
+                object,
+                index,
+                value,
+                ..
+            } => {
+                let id = format!("set_index_{count}");
+                *count += 1;
+                res.push_str(&format!("{id}\n"));
+                res.push_str(&format!("\t{id} [label=\"[]=\"]\n"));
+                res.push_str(&format!("\t{id} -> {}", object._graph(count)));
+                res.push_str(&format!("\t{id} -> {}", index._graph(count)));
+                res.push_str(&format!("\t{id} -> {}", value._graph(count)));
+            }
+            Self::Update {
+                target,
+                operator,
+                value,
+                postfix,
+            } => {
+                let id = format!("update_{count}");
+                *count += 1;
+                let label = if *postfix {
+                    format!("post{}", operator.lexeme)
+                } else {
+                    operator.lexeme.clone()
+                };
+                res.push_str(&format!("{id}\n"));
+                res.push_str(&format!("\t{id} [label=\"{label}\"]\n"));
+                res.push_str(&format!("\t{id} -> {}", target._graph(count)));
+                if !matches!(operator.lexeme.as_str(), "++" | "--") {
+                    res.push_str(&format!("\t{id} -> {}", value._graph(count)));
+                }
+            }
             _ => unimplemented!(),
         }
         res
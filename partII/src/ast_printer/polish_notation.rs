@@ -17,6 +17,19 @@ impl Expr {
                     right.polish_notation()
                 ));
             }
+            Self::Conditional {
+                condition,
+                then_branch,
+                else_branch,
+                ..
+            } => {
+                res.push_str(&format!(
+                    "(?: {} {} {})",
+                    condition.polish_notation(),
+                    then_branch.polish_notation(),
+                    else_branch.polish_notation()
+                ));
+            }
             Self::Grouping { expression } => {
                 res.push_str(&format!("(group {})", expression.polish_notation()));
             }
@@ -28,6 +41,61 @@ impl Expr {
                     right.polish_notation()
                 ));
             }
+            Self::List { elements, .. } => {
+                res.push_str("(list");
+                for element in elements {
+                    res.push_str(&format!(" {}", element.polish_notation()));
+                }
+                res.push(')');
+            }
+            Self::Map { entries, .. } => {
+                res.push_str("(map");
+                for (key, value) in entries {
+                    res.push_str(&format!(
+                        " ({} {})",
+                        key.polish_notation(),
+                        value.polish_notation()
+                    ));
+                }
+                res.push(')');
+            }
+            Self::Index { object, index, .. } => {
+                res.push_str(&format!(
+                    "([] {} {})",
+                    object.polish_notation(),
+                    index.polish_notation()
+                ));
+            }
+            Self::SetIndex {
+                object,
+                index,
+                value,
+                ..
+            } => {
+                res.push_str(&format!(
+                    "([]= {} {} {})",
+                    object.polish_notation(),
+                    index.polish_notation(),
+                    value.polish_notation()
+                ));
+            }
+            Self::Update {
+                target,
+                operator,
+                value,
+                postfix,
+            } => {
+                let operator = &operator.lexeme;
+                let target = target.polish_notation();
+                match operator.as_str() {
+                    "++" | "--" if *postfix => res.push_str(&format!("(post{operator} {target})")),
+                    "++" | "--" => res.push_str(&format!("({operator} {target})")),
+                    _ => res.push_str(&format!(
+                        "({operator} {target} {})",
+                        value.polish_notation()
+                    )),
+                }
+            }
             _ => unimplemented!(),
         }
 
@@ -17,8 +17,21 @@ impl Expr {
                     operator.lexeme,
                 ));
             }
+            Self::Conditional {
+                condition,
+                then_branch,
+                else_branch,
+                ..
+            } => {
+                res.push_str(&format!(
+                    "{} {} {} ?:",
+                    condition.reverse_polish_notation(),
+                    then_branch.reverse_polish_notation(),
+                    else_branch.reverse_polish_notation(),
+                ));
+            }
             Self::Grouping { expression } => {
-                res.push_str(&format!("{}", expression.reverse_polish_notation()));
+                res.push_str(&expression.reverse_polish_notation());
             }
             Self::Literal { value } => res.push_str(&value.to_string()),
             Self::Unary { right, operator } => {
@@ -28,6 +41,59 @@ impl Expr {
                     operator.lexeme,
                 ));
             }
+            Self::List { elements, .. } => {
+                for element in elements {
+                    res.push_str(&format!("{} ", element.reverse_polish_notation()));
+                }
+                res.push_str(&format!("list({})", elements.len()));
+            }
+            Self::Map { entries, .. } => {
+                for (key, value) in entries {
+                    res.push_str(&format!(
+                        "{} {} ",
+                        key.reverse_polish_notation(),
+                        value.reverse_polish_notation()
+                    ));
+                }
+                res.push_str(&format!("map({})", entries.len()));
+            }
+            Self::Index { object, index, .. } => {
+                res.push_str(&format!(
+                    "{} {} []",
+                    object.reverse_polish_notation(),
+                    index.reverse_polish_notation(),
+                ));
+            }
+            Self::SetIndex {
+                object,
+                index,
+                value,
+                ..
+            } => {
+                res.push_str(&format!(
+                    "{} {} {} []=",
+                    object.reverse_polish_notation(),
+                    index.reverse_polish_notation(),
+                    value.reverse_polish_notation(),
+                ));
+            }
+            Self::Update {
+                target,
+                operator,
+                value,
+                postfix,
+            } => {
+                let operator = &operator.lexeme;
+                let target = target.reverse_polish_notation();
+                match operator.as_str() {
+                    "++" | "--" if *postfix => res.push_str(&format!("{target} post{operator}")),
+                    "++" | "--" => res.push_str(&format!("{target} {operator}")),
+                    _ => res.push_str(&format!(
+                        "{target} {} {operator}",
+                        value.reverse_polish_notation()
+                    )),
+                }
+            }
             _ => unimplemented!(),
         }
 
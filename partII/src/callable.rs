@@ -1,12 +1,12 @@
 use std::rc::Rc;
 
 use crate::{
-    environment::Environment,
+    environment::{Environment, SharedEnvironment},
     error::RuntimeError,
     instance::Instance,
-    interpreter::Interpreter,
+    interpreter::{ControlFlow, Interpreter},
     stmt::Stmt,
-    token::{Token, TokenType},
+    token::Token,
     value::Value,
 };
 
@@ -14,16 +14,18 @@ use anyhow::anyhow;
 
 pub trait Callable: std::fmt::Debug {
     fn call(
-        &mut self,
+        &self,
         interpreter: &mut Interpreter,
         arguments: Vec<Value>,
     ) -> Result<Value, RuntimeError>;
     fn arity(&self) -> usize;
+    /// Name of the callable, as shown in stack traces.
+    fn name(&self) -> &str;
 }
 
 impl Callable for Value {
     fn call(
-        &mut self,
+        &self,
         interpreter: &mut Interpreter,
         arguments: Vec<Value>,
     ) -> Result<Value, RuntimeError> {
@@ -33,12 +35,11 @@ impl Callable for Value {
                 fun.arity(),
                 arguments.len()
             ))?,
-            Self::Callable(fun) => unsafe {
-                Rc::get_mut_unchecked(fun).call(interpreter, arguments)
-            },
+            Self::Callable(fun) => fun.call(interpreter, arguments),
             // Should I copy paste the code above?
-            Self::Class(class) => Value::Callable(Rc::new(class.clone()) as Rc<dyn Callable>)
-                .call(interpreter, arguments),
+            Self::Class(class) => {
+                Value::Callable(class.clone() as Rc<dyn Callable>).call(interpreter, arguments)
+            }
             _ => Err(anyhow!("Can only call functions or classes."))?,
         }
     }
@@ -49,6 +50,14 @@ impl Callable for Value {
             _ => panic!("Called arity on a non function value"),
         }
     }
+
+    fn name(&self) -> &str {
+        match self {
+            Self::Callable(fun) => fun.name(),
+            Self::Class(class) => &class.name,
+            _ => "",
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -58,17 +67,38 @@ pub struct Function {
     pub body: Rc<Vec<Stmt>>,
 
     pub is_initializer: bool,
-    pub closure: Option<Environment>,
+    pub closure: Option<SharedEnvironment>,
+    /// The `///` comments right before the declaration.
+    pub doc: Option<String>,
+}
+
+/// Declarations are compared by their syntax, closures by identity.
+impl PartialEq for Function {
+    fn eq(&self, other: &Self) -> bool {
+        let same_closure = match (&self.closure, &other.closure) {
+            (Some(left), Some(right)) => Rc::ptr_eq(left, right),
+            (left, right) => left.is_none() && right.is_none(),
+        };
+        self.name == other.name
+            && self.params == other.params
+            && self.body == other.body
+            && self.is_initializer == other.is_initializer
+            && self.doc == other.doc
+            && same_closure
+    }
 }
 
 impl Function {
-    pub fn evaluate(&self, env: &mut Environment) -> Result<(), RuntimeError> {
-        let value = self.clone().with_environment(env.clone()).to_value();
-        env.define(self.name.lexeme.to_string(), value);
+    pub fn evaluate(&self, interpreter: &mut Interpreter) -> Result<(), RuntimeError> {
+        let value = self
+            .clone()
+            .with_environment(interpreter.env.clone())
+            .to_value();
+        interpreter.define(&self.name.lexeme, value);
         Ok(())
     }
 
-    pub fn with_environment(self, env: Environment) -> Self {
+    pub fn with_environment(self, env: SharedEnvironment) -> Self {
         Self {
             closure: Some(env),
             ..self
@@ -80,21 +110,30 @@ impl Function {
     }
 
     pub fn bind(&self, instance: Instance) -> Self {
-        let mut environment = Environment::new();
+        let mut environment = self.environment();
         environment.define("this", instance);
         Self {
             name: self.name.clone(),
             params: self.params.clone(),
             body: self.body.clone(),
             is_initializer: self.is_initializer,
-            closure: Some(environment),
+            closure: Some(environment.shared()),
+            doc: self.doc.clone(),
+        }
+    }
+
+    /// A new scope enclosed by the closure of the function.
+    fn environment(&self) -> Environment {
+        match &self.closure {
+            Some(closure) => Environment::enclosed_by(closure.clone()),
+            None => Environment::new(),
         }
     }
 }
 
 impl Callable for Function {
     fn call(
-        &mut self,
+        &self,
         interpreter: &mut Interpreter,
         arguments: Vec<Value>,
     ) -> Result<Value, RuntimeError> {
@@ -106,66 +145,29 @@ impl Callable for Function {
             ))?;
         }
 
-        // we create a new interpreter just for the execution of this function
-        let mut local_interpreter = Interpreter::new();
-
-        // we extract the global env and the locals from the original interpreter
-        std::mem::swap(&mut local_interpreter.locals, &mut interpreter.locals);
-        std::mem::swap(local_interpreter.globals_mut(), interpreter.globals_mut());
-
-        let is_closure = self.closure.is_some();
-
-        // extract the env of the closure
-        let mut env = Environment::new();
-        let closure = self.closure.as_mut().unwrap_or(&mut env);
-        let closure = std::mem::take(closure);
-
-        local_interpreter.enclosed_by(closure);
-
+        let mut env = self.environment();
         for (param, arg) in self.params.iter().zip(arguments) {
-            local_interpreter.define(param.lexeme.to_string(), arg);
+            env.define(&param.lexeme, arg);
         }
 
-        let result = match Stmt::Block(self.body.clone()).evaluate(&mut local_interpreter) {
-            Ok(()) => Ok(Value::Nil),
-            Err(RuntimeError::Return(value)) => Ok(value),
-            Err(e) => Err(e),
+        let result = match interpreter.execute_block(&self.body, env) {
+            Ok(()) => Value::Nil,
+            Err(ControlFlow::Return(value)) => value,
+            Err(ControlFlow::Error(error)) => return Err(error),
+            Err(flow) => unreachable!("The resolver let a {flow:?} escape its function"),
         };
 
-        // we restore the global env and the locals
-        std::mem::swap(local_interpreter.globals_mut(), interpreter.globals_mut());
-        std::mem::swap(&mut local_interpreter.locals, &mut interpreter.locals);
-
-        // restore the env of the closure
-        let env = local_interpreter.env.pop();
-
-        if is_closure {
-            self.closure = Some(env);
-        }
-
-        let result = result?;
-
-        if self.is_initializer && self.closure.is_some() {
-            Ok(self
-                .closure
-                .as_ref()
-                .unwrap()
-                .get_at(
-                    0,
-                    &Token {
-                        ty: TokenType::This,
-                        lexeme: "this".to_string(),
-                        line: 0,
-                    },
-                )
-                .unwrap()
-                .clone())
-        } else {
-            Ok(result)
+        match &self.closure {
+            Some(closure) if self.is_initializer => closure.borrow().get_at(0, "this"),
+            _ => Ok(result),
         }
     }
 
     fn arity(&self) -> usize {
         self.params.len()
     }
+
+    fn name(&self) -> &str {
+        &self.name.lexeme
+    }
 }
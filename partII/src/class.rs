@@ -1,4 +1,4 @@
-use std::{collections::HashMap, fmt::Display};
+use std::{collections::HashMap, fmt::Display, rc::Rc};
 
 use crate::{
     callable::{Callable, Function},
@@ -9,19 +9,19 @@ use crate::{
 pub struct Class {
     pub name: String,
     pub methods: HashMap<String, Function>,
-    pub superclass: Option<Box<Class>>,
+    pub superclass: Option<Rc<Class>>,
 }
 
 impl Class {
     pub fn new(
         name: String,
         methods: HashMap<String, Function>,
-        superclass: Option<Class>,
+        superclass: Option<Rc<Class>>,
     ) -> Self {
         Class {
             name,
             methods,
-            superclass: superclass.map(Box::new),
+            superclass,
         }
     }
 
@@ -44,7 +44,7 @@ impl Display for Class {
 
 impl Callable for Class {
     fn call(
-        &mut self,
+        &self,
         interpreter: &mut crate::interpreter::Interpreter,
         arguments: Vec<crate::value::Value>,
     ) -> Result<crate::value::Value, crate::error::RuntimeError> {
@@ -61,4 +61,8 @@ impl Callable for Class {
     fn arity(&self) -> usize {
         self.find_method("init").map(Callable::arity).unwrap_or(0)
     }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
 }
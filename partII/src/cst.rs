@@ -0,0 +1,139 @@
+use std::fmt::Display;
+
+use crate::{error::ParserErrors, parser::Parser, stmt::Stmt, token::Token};
+
+/// Kind of a node of the concrete syntax tree, after the syntax it covers
+/// rather than the `Stmt` or `Expr` parsed from it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NodeKind {
+    Script,
+    // Statements
+    Block,
+    Break,
+    Class,
+    Continue,
+    Expression,
+    For,
+    /// A function declaration or a method.
+    Function,
+    If,
+    Print,
+    Return,
+    Var,
+    While,
+    // Expressions
+    /// `=` and the compound assignments.
+    Assign,
+    /// Binary operators, `,` included.
+    Binary,
+    Call,
+    Conditional,
+    Get,
+    Grouping,
+    Index,
+    /// A string with `${}` in it.
+    Interpolation,
+    Lambda,
+    List,
+    Literal,
+    Logical,
+    Map,
+    /// `x++` and `x--`.
+    Postfix,
+    Super,
+    This,
+    /// Prefix operators, `++` and `--` included.
+    Unary,
+    Variable,
+}
+
+/// A token with the whitespace and comments right before it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Leaf {
+    pub trivia: Vec<Token>,
+    pub token: Token,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Node {
+    Leaf(Leaf),
+    Tree(Tree),
+}
+
+/// A lossless syntax tree: printing it gives back the source it was parsed
+/// from, byte for byte, comments and parentheses included.
+///
+/// Built by [`Parser::parse_cst`] from tokens scanned with
+/// [`Scanner::with_trivia`](crate::scanner::Scanner::with_trivia).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tree {
+    pub kind: NodeKind,
+    pub children: Vec<Node>,
+}
+
+impl Tree {
+    /// All the tokens under this node, trivia included, in source order.
+    pub fn tokens(&self) -> impl Iterator<Item = &Token> {
+        let mut tokens = Vec::new();
+        self.collect_tokens(&mut tokens);
+        tokens.into_iter()
+    }
+
+    fn collect_tokens<'a>(&'a self, tokens: &mut Vec<&'a Token>) {
+        for child in &self.children {
+            match child {
+                Node::Leaf(leaf) => {
+                    tokens.extend(&leaf.trivia);
+                    tokens.push(&leaf.token);
+                }
+                Node::Tree(tree) => tree.collect_tokens(tokens),
+            }
+        }
+    }
+
+    /// Parse the tokens of a `Script` again, into the abstract syntax tree
+    /// [`Parser::parse`] gives for the same source.
+    ///
+    /// The nodes aren't converted one by one: they only record how the parser
+    /// grouped the tokens, and the parser stays the one place that knows how
+    /// the syntax desugars into `Stmt` and `Expr`.
+    pub fn reparse(&self) -> Result<Vec<Stmt>, ParserErrors> {
+        Parser::new(self.tokens().cloned().collect()).parse()
+    }
+}
+
+impl Display for Tree {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.tokens()
+            .try_for_each(|token| f.write_str(&token.lexeme))
+    }
+}
+
+/// Assembles the tree while the parser consumes tokens, nodes are closed
+/// once all their children are known.
+#[derive(Debug, Default)]
+pub(crate) struct Builder {
+    children: Vec<Node>,
+    /// Position in `children` of the node starting with each token consumed.
+    starts: Vec<usize>,
+}
+
+impl Builder {
+    pub fn token(&mut self, trivia: Vec<Token>, token: Token) {
+        self.starts.push(self.children.len());
+        self.children.push(Node::Leaf(Leaf { trivia, token }));
+    }
+
+    /// Group everything from the `start`th token consumed into a node.
+    pub fn node(&mut self, kind: NodeKind, start: usize) {
+        let children = self.children.split_off(self.starts[start]);
+        self.children.push(Node::Tree(Tree { kind, children }));
+    }
+
+    pub fn finish(self) -> Tree {
+        Tree {
+            kind: NodeKind::Script,
+            children: self.children,
+        }
+    }
+}
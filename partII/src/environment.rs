@@ -1,10 +1,13 @@
-use std::collections::HashMap;
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
 
 use crate::{error::RuntimeError, token::Token, value::Value};
 
-#[derive(Default, Debug, Clone)]
+/// A scope shared between the interpreter and all the closures created in it.
+pub type SharedEnvironment = Rc<RefCell<Environment>>;
+
+#[derive(Default, Debug)]
 pub struct Environment {
-    pub enclosing: Option<Box<Environment>>,
+    pub enclosing: Option<SharedEnvironment>,
     values: HashMap<String, Value>,
 }
 
@@ -13,100 +16,90 @@ impl Environment {
         Self::default()
     }
 
-    pub fn enclose(&mut self, enclosing: Self) {
-        self.enclosing = Some(Box::new(enclosing));
-    }
-
-    pub fn enclosed_by(&mut self, mut enclosing: Self) {
-        std::mem::swap(self, &mut enclosing);
-        self.enclosing = Some(Box::new(enclosing));
-    }
-
-    /// Reverse a `enclosed_by`
-    pub fn declose(&mut self) {
-        let inner = *self.enclosing.take().unwrap();
-        // drop the old env
-        let _ = std::mem::replace(self, inner);
-    }
-
-    pub fn destroy(self) -> Option<Environment> {
-        self.enclosing.map(|env| *env)
+    pub fn enclosed_by(enclosing: SharedEnvironment) -> Self {
+        Self {
+            enclosing: Some(enclosing),
+            values: HashMap::new(),
+        }
     }
 
-    pub fn pop(mut self) -> Environment {
-        self.enclosing = None;
-        self
+    pub fn shared(self) -> SharedEnvironment {
+        Rc::new(RefCell::new(self))
     }
 
     pub fn define(&mut self, name: impl AsRef<str>, value: impl Into<Value>) {
         self.values.insert(name.as_ref().to_string(), value.into());
     }
 
-    pub fn assign(&mut self, name: &Token, value: Value) -> Result<(), RuntimeError> {
-        *self.get_mut(name)? = value;
-        Ok(())
+    /// Whether `name` is defined in this environment, not the enclosing ones.
+    pub fn contains(&self, name: impl AsRef<str>) -> bool {
+        self.values.contains_key(name.as_ref())
     }
 
-    pub fn globals(&self) -> &Self {
-        if self.enclosing.is_some() {
-            self.enclosing.as_ref().unwrap().globals()
+    pub fn assign(&mut self, name: &Token, value: Value) -> Result<(), RuntimeError> {
+        if let Some(variable) = self.values.get_mut(&name.lexeme) {
+            *variable = value;
+            Ok(())
+        } else if let Some(enclosing) = &self.enclosing {
+            enclosing.borrow_mut().assign(name, value)
         } else {
-            self
+            Err(anyhow::anyhow!("Undefined variable `{}`.", name.lexeme))?
         }
     }
 
-    pub fn globals_mut(&mut self) -> &mut Self {
-        if self.enclosing.is_some() {
-            self.enclosing.as_mut().unwrap().globals_mut()
+    pub fn get(&self, name: impl AsRef<str>) -> Result<Value, RuntimeError> {
+        let name = name.as_ref();
+        if let Some(value) = self.values.get(name) {
+            Ok(value.clone())
+        } else if let Some(enclosing) = &self.enclosing {
+            enclosing.borrow().get(name)
         } else {
-            self
+            Err(anyhow::anyhow!("Undefined variable `{}`.", name))?
         }
     }
 
-    pub fn get(&self, name: impl AsRef<str>) -> Result<&Value, RuntimeError> {
+    /// Get a variable declared `distance` scopes above this one.
+    pub fn get_at(&self, distance: usize, name: impl AsRef<str>) -> Result<Value, RuntimeError> {
         let name = name.as_ref();
-        Ok(self
-            .values
-            .get(name)
-            .or_else(|| {
-                self.enclosing
-                    .as_ref()
-                    .map(|env| env.get(name).ok())
-                    .flatten()
-            })
-            .ok_or(anyhow::anyhow!("Undefined variable `{}`.", name))?)
-    }
-
-    pub fn get_mut(&mut self, name: &Token) -> Result<&mut Value, RuntimeError> {
-        Ok(self
-            .values
-            .get_mut(&name.lexeme)
-            .or_else(|| {
-                self.enclosing
-                    .as_mut()
-                    .map(|env| env.get_mut(name).ok())
-                    .flatten()
-            })
-            .ok_or(anyhow::anyhow!("Undefined variable `{}`.", name.lexeme))?)
-    }
-
-    pub fn get_at(&self, distance: usize, name: impl AsRef<str>) -> Result<&Value, RuntimeError> {
         if distance == 0 {
-            self.get(name)
+            Ok(self
+                .values
+                .get(name)
+                .ok_or_else(|| anyhow::anyhow!("Undefined variable `{}`.", name))?
+                .clone())
         } else {
-            self.get_at(distance - 1, name)
+            self.enclosing()?.borrow().get_at(distance - 1, name)
         }
     }
 
-    pub fn get_at_mut(
+    /// Assign a variable declared `distance` scopes above this one.
+    pub fn assign_at(
         &mut self,
         distance: usize,
         name: &Token,
-    ) -> Result<&mut Value, RuntimeError> {
+        value: Value,
+    ) -> Result<(), RuntimeError> {
         if distance == 0 {
-            self.get_mut(name)
+            let variable = self
+                .values
+                .get_mut(&name.lexeme)
+                .ok_or_else(|| anyhow::anyhow!("Undefined variable `{}`.", name.lexeme))?;
+            *variable = value;
+            Ok(())
         } else {
-            self.get_at_mut(distance - 1, name)
+            self.enclosing()?
+                .borrow_mut()
+                .assign_at(distance - 1, name, value)
         }
     }
+
+    /// A resolved distance can outlive its expression in the REPL, where the
+    /// address of a dropped expression may be reused by a later one, so a scope
+    /// that is missing is reported instead of trusted.
+    fn enclosing(&self) -> Result<&SharedEnvironment, RuntimeError> {
+        Ok(self
+            .enclosing
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Variable resolved outside of its scope."))?)
+    }
 }
@@ -1,8 +1,8 @@
 use thiserror::Error;
 
-use std::io;
+use std::{fmt::Write, io};
 
-use crate::{token::Token, value::Value};
+use crate::token::{Span, Token};
 
 pub type Result<T> = std::result::Result<T, Error>;
 
@@ -15,15 +15,127 @@ pub enum Error {
     #[error(transparent)]
     Parser(#[from] ParserErrors),
     #[error(transparent)]
+    Resolver(#[from] ResolverErrors),
+    #[error(transparent)]
+    Warnings(#[from] ResolverWarnings),
+    #[error(transparent)]
     Runtime(#[from] RuntimeError),
     #[error("Unexpected error: {0}")]
     Unexpected(#[from] anyhow::Error),
 }
 
+impl Error {
+    /// Render the error with the snippets of source code it points to.
+    pub fn report(&self, source: &Source) -> String {
+        let mut report = String::new();
+
+        match self {
+            Self::Scanner(ScannerErrors(errors)) => {
+                for error in errors {
+                    source.snippet(&mut report, "error", &error.to_string(), error.span());
+                }
+            }
+            Self::Parser(ParserErrors(errors)) => {
+                for error in errors {
+                    let message = error.to_string();
+                    source.snippet(&mut report, "error", &message, error.token().span);
+                }
+            }
+            Self::Resolver(ResolverErrors(errors)) => {
+                for error in errors {
+                    let message = error.to_string();
+                    source.snippet(&mut report, "error", &message, error.token().span);
+                }
+            }
+            // the warnings were promoted to errors
+            Self::Warnings(warnings) => report = warnings.report(source, "error"),
+            Self::Runtime(RuntimeError::Unexpected {
+                error,
+                token: Some(token),
+                trace,
+            }) => {
+                source.snippet(&mut report, "error", &error.to_string(), token.span);
+
+                let mut line = token.span.line;
+                for frame in trace {
+                    let _ = writeln!(report, "[line {line}] in {}()", frame.function);
+                    line = frame.line;
+                }
+                let _ = writeln!(report, "[line {line}] in script");
+            }
+            error => {
+                let _ = writeln!(report, "error: {error}");
+            }
+        }
+
+        report
+    }
+
+    /// The exit code to use when the error ends the program, following `sysexits.h`.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Self::Setup(SetupError::Usage | SetupError::FmtUsage) => 64,
+            Self::Scanner(_) | Self::Parser(_) | Self::Resolver(_) | Self::Warnings(_) => 65,
+            Self::Setup(SetupError::Io(_)) => 74,
+            Self::Runtime(_) | Self::Unexpected(_) => 70,
+        }
+    }
+}
+
+/// The code being run, used to show where an error happened.
+#[derive(Debug, Clone, Copy)]
+pub struct Source<'a> {
+    pub name: &'a str,
+    pub code: &'a str,
+}
+
+impl<'a> Source<'a> {
+    pub fn new(name: &'a str, code: &'a str) -> Self {
+        Self { name, code }
+    }
+
+    /// Write a `file:line:col` header followed by the offending line with the
+    /// token underlined.
+    ///
+    /// ```text
+    /// error: Got `}`. Expect `;` after value.
+    ///  --> simple.lox:3:12
+    ///   |
+    /// 3 | print a + b
+    ///   |            ^
+    /// ```
+    fn snippet(&self, f: &mut String, level: &str, message: &str, span: Span) {
+        let _ = writeln!(f, "{level}: {message}");
+        // synthetic tokens, like the `this` of an initializer, aren't in the source
+        if span.line == 0 {
+            return;
+        }
+
+        let line = self.code.lines().nth(span.line - 1).unwrap_or("");
+        let columns = span.columns(self.code);
+        let gutter = " ".repeat(span.line.to_string().len());
+
+        let _ = writeln!(f, "{gutter}--> {}:{}:{}", self.name, span.line, span.column);
+        let _ = writeln!(f, "{gutter} |");
+        let _ = writeln!(f, "{} | {line}", span.line);
+        let _ = writeln!(
+            f,
+            "{gutter} | {}{}",
+            " ".repeat(columns.start - 1),
+            "^".repeat(columns.len())
+        );
+    }
+}
+
 #[derive(Error, Debug)]
 pub enum SetupError {
-    #[error("Usage {} [script]", std::env::args().nth(0).unwrap())]
+    #[error("Usage {} [--deny-warnings] [script]", std::env::args().nth(0).unwrap())]
     Usage,
+    #[error(
+        "Usage {} fmt [--check] [--indent <width>] [--line-length <width>] [files]",
+        std::env::args().nth(0).unwrap()
+    )]
+    FmtUsage,
     #[error("IO Error: ")]
     Io(#[from] io::Error),
 }
@@ -45,11 +157,33 @@ impl std::error::Error for ScannerErrors {}
 #[derive(Error, Debug)]
 pub enum ScannerError {
     #[error("Unexpected character `{0}`.")]
-    Character(char),
+    Character(char, Span),
     #[error("Unterminated string.")]
-    String,
+    String(Span),
+    #[error("Unterminated interpolation, expect `}}` to close `${{`.")]
+    Interpolation(Span),
+    #[error("Unterminated block comment, expect `*/` to close `/*`.")]
+    Comment(Span),
+    #[error("Unknown escape sequence `{0}`.")]
+    Escape(String, Span),
+    #[error("Invalid unicode escape `{0}`, expect 1 to 6 hexadecimal digits of a scalar value.")]
+    UnicodeEscape(String, Span),
     #[error("Could not convert {0} to a number: {1}")]
-    Number(String, std::num::ParseFloatError),
+    Number(String, std::num::ParseFloatError, Span),
+}
+
+impl ScannerError {
+    pub fn span(&self) -> Span {
+        match self {
+            Self::Character(_, span)
+            | Self::String(span)
+            | Self::Interpolation(span)
+            | Self::Comment(span)
+            | Self::Escape(_, span)
+            | Self::UnicodeEscape(_, span)
+            | Self::Number(_, _, span) => *span,
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -69,21 +203,200 @@ impl std::error::Error for ParserErrors {}
 #[derive(Error, Debug)]
 pub enum ParserError {
     #[error("Expecting expression.")]
-    ExpectingExpression,
+    ExpectingExpression(Token),
     #[error("Can't have more than 255 arguments.")]
-    TooManyArguments,
+    TooManyArguments(Token),
     #[error("Can't have more than 255 parameters.")]
-    TooManyParameters,
+    TooManyParameters(Token),
     #[error("Invalid assignment target {0}.")]
     InvalidAssignmentTarget(Token),
-    #[error("{0}")]
-    Consume(String),
+    #[error("Got `{}`. {1}", .0.lexeme)]
+    Consume(Token, String),
+}
+
+impl ParserError {
+    /// The token the parser was looking at when the error happened.
+    pub fn token(&self) -> &Token {
+        match self {
+            Self::ExpectingExpression(token)
+            | Self::TooManyArguments(token)
+            | Self::TooManyParameters(token)
+            | Self::InvalidAssignmentTarget(token)
+            | Self::Consume(token, _) => token,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ResolverErrors(pub Vec<ResolverError>);
+
+impl std::fmt::Display for ResolverErrors {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for error in &self.0 {
+            writeln!(f, "{error}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ResolverErrors {}
+
+#[derive(Error, Debug)]
+pub enum ResolverError {
+    #[error("Already a variable with this name in this scope.")]
+    AlreadyDeclared(Token),
+    #[error("Can't read local variable in its own initializer.")]
+    ReadInOwnInitializer(Token),
+    #[error("Can't return from top-level code.")]
+    TopLevelReturn(Token),
+    #[error("Can't return a value from an initializer.")]
+    ReturnFromInitializer(Token),
+    #[error("Can't use `this` outside of a class.")]
+    ThisOutsideClass(Token),
+    #[error("Can't use `super` outside of a class.")]
+    SuperOutsideClass(Token),
+    #[error("Can't use `super` in a class with no superclass.")]
+    SuperWithoutSuperclass(Token),
+    #[error("A class can't inherit from itself.")]
+    InheritFromItself(Token),
+    #[error("Can't use `{}` outside of a loop.", .0.lexeme)]
+    OutsideLoop(Token),
+}
+
+impl ResolverError {
+    pub fn token(&self) -> &Token {
+        match self {
+            Self::AlreadyDeclared(token)
+            | Self::ReadInOwnInitializer(token)
+            | Self::TopLevelReturn(token)
+            | Self::ReturnFromInitializer(token)
+            | Self::ThisOutsideClass(token)
+            | Self::SuperOutsideClass(token)
+            | Self::SuperWithoutSuperclass(token)
+            | Self::InheritFromItself(token)
+            | Self::OutsideLoop(token) => token,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ResolverWarnings(pub Vec<ResolverWarning>);
+
+impl ResolverWarnings {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Render every warning at the given level, `warning` or `error`.
+    pub fn report(&self, source: &Source, level: &str) -> String {
+        let mut report = String::new();
+        for warning in &self.0 {
+            let message = warning.to_string();
+            source.snippet(&mut report, level, &message, warning.token().span);
+        }
+        report
+    }
+}
+
+impl std::fmt::Display for ResolverWarnings {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for warning in &self.0 {
+            writeln!(f, "{warning}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ResolverWarnings {}
+
+#[derive(Error, Debug)]
+pub enum ResolverWarning {
+    #[error("Local variable `{}` is never read.", .0.lexeme)]
+    UnusedLocal(Token),
+    #[error("Parameter `{}` is never used.", .0.lexeme)]
+    UnusedParameter(Token),
+    #[error("Unreachable code after `{}`.", .keyword.lexeme)]
+    UnreachableCode {
+        keyword: Token,
+        /// A token of the first unreachable statement, or `keyword` when the
+        /// statement keeps none.
+        code: Token,
+    },
+    #[error("Local variable `{}` shadows an outer binding.", .0.lexeme)]
+    Shadowing(Token),
+}
+
+impl ResolverWarning {
+    pub fn token(&self) -> &Token {
+        match self {
+            Self::UnusedLocal(token)
+            | Self::UnusedParameter(token)
+            | Self::UnreachableCode { code: token, .. }
+            | Self::Shadowing(token) => token,
+        }
+    }
 }
 
 #[derive(Error, Debug)]
 pub enum RuntimeError {
-    #[error("Return called outside of a function.")]
-    Return(Value),
-    #[error("Unexpected error: {0}")]
-    Unexpected(#[from] anyhow::Error),
+    #[error("{error}")]
+    Unexpected {
+        error: anyhow::Error,
+        /// The operator or call that raised the error.
+        token: Option<Token>,
+        /// The Lox functions the error unwound through, innermost first.
+        trace: Vec<Frame>,
+    },
+}
+
+impl RuntimeError {
+    /// Record where the error was raised, unless an inner expression already did.
+    pub fn at(mut self, at: Option<&Token>) -> Self {
+        if let Self::Unexpected {
+            token: token @ None,
+            ..
+        } = &mut self
+        {
+            *token = at.cloned();
+        }
+        self
+    }
+
+    /// Record that the error unwound out of a call to `function` made by `paren`.
+    ///
+    /// Errors without a location were raised by the call itself (bad arity,
+    /// failing native function, ...) and don't get a frame.
+    pub fn called_from(mut self, function: &str, paren: &Token) -> Self {
+        if let Self::Unexpected {
+            token: Some(_),
+            trace,
+            ..
+        } = &mut self
+        {
+            trace.push(Frame {
+                function: function.to_string(),
+                line: paren.span.line,
+            });
+        }
+        self
+    }
+}
+
+impl From<anyhow::Error> for RuntimeError {
+    fn from(error: anyhow::Error) -> Self {
+        Self::Unexpected {
+            error,
+            token: None,
+            trace: Vec::new(),
+        }
+    }
+}
+
+/// A Lox function call that was running when a runtime error happened.
+#[derive(Debug, Clone)]
+pub struct Frame {
+    /// Name of the called function.
+    pub function: String,
+    /// Line of the call in the caller.
+    pub line: usize,
 }
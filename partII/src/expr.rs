@@ -1,8 +1,8 @@
 use std::fmt::Display;
 
-use crate::{token::Token, value::Value};
+use crate::{callable::Function, error::ParserError, token::Token, value::Value};
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Expr {
     Assign {
         name: Token,
@@ -18,6 +18,13 @@ pub enum Expr {
         paren: Token,
         arguments: Vec<Expr>,
     },
+    /// `condition ? then_branch : else_branch`
+    Conditional {
+        condition: Box<Expr>,
+        question: Token,
+        then_branch: Box<Expr>,
+        else_branch: Box<Expr>,
+    },
     Get {
         object: Box<Expr>,
         name: Token,
@@ -25,6 +32,17 @@ pub enum Expr {
     Grouping {
         expression: Box<Expr>,
     },
+    Index {
+        object: Box<Expr>,
+        bracket: Token,
+        index: Box<Expr>,
+    },
+    /// An anonymous `fun (params) { body }`, named `lambda`.
+    Lambda(Function),
+    List {
+        bracket: Token,
+        elements: Vec<Expr>,
+    },
     Literal {
         value: Value,
     },
@@ -33,11 +51,21 @@ pub enum Expr {
         operator: Token,
         right: Box<Expr>,
     },
+    Map {
+        brace: Token,
+        entries: Vec<(Expr, Expr)>,
+    },
     Set {
         object: Box<Expr>,
         name: Token,
         value: Box<Expr>,
     },
+    SetIndex {
+        object: Box<Expr>,
+        bracket: Token,
+        index: Box<Expr>,
+        value: Box<Expr>,
+    },
     Super {
         keyword: Token,
         method: Token,
@@ -45,6 +73,15 @@ pub enum Expr {
     This {
         keyword: Token,
     },
+    /// `target op= value`, `++target` or `target++`, where the target is a
+    /// variable, a property or an index. Increments have a `value` of 1.
+    Update {
+        target: Box<Expr>,
+        operator: Token,
+        value: Box<Expr>,
+        /// Whether it yields the value before the update.
+        postfix: bool,
+    },
     Unary {
         operator: Token,
         right: Box<Expr>,
@@ -71,6 +108,24 @@ impl Expr {
         }
     }
 
+    /// Build an update of `target`, which must be assignable.
+    pub fn update(
+        target: Expr,
+        operator: Token,
+        value: Expr,
+        postfix: bool,
+    ) -> Result<Self, ParserError> {
+        match target {
+            Self::Variable { .. } | Self::Get { .. } | Self::Index { .. } => Ok(Self::Update {
+                target: Box::new(target),
+                operator,
+                value: Box::new(value),
+                postfix,
+            }),
+            _ => Err(ParserError::InvalidAssignmentTarget(operator)),
+        }
+    }
+
     pub fn group(expr: Expr) -> Self {
         Self::Grouping {
             expression: Box::new(expr),
@@ -90,6 +145,29 @@ impl Expr {
         }
     }
 
+    /// The token errors raised while evaluating this expression should point to.
+    pub fn token(&self) -> Option<&Token> {
+        match self {
+            Self::Assign { name, .. }
+            | Self::Get { name, .. }
+            | Self::Set { name, .. }
+            | Self::Variable { name } => Some(name),
+            Self::Binary { operator, .. }
+            | Self::Logical { operator, .. }
+            | Self::Unary { operator, .. }
+            | Self::Update { operator, .. } => Some(operator),
+            Self::Call { paren, .. } => Some(paren),
+            Self::Conditional { question, .. } => Some(question),
+            Self::Lambda(function) => Some(&function.name),
+            Self::Index { bracket, .. }
+            | Self::List { bracket, .. }
+            | Self::SetIndex { bracket, .. } => Some(bracket),
+            Self::Map { brace, .. } => Some(brace),
+            Self::Super { keyword, .. } | Self::This { keyword } => Some(keyword),
+            Self::Grouping { .. } | Self::Literal { .. } => None,
+        }
+    }
+
     pub fn unwrap_variable(&self) -> &Token {
         match self {
             Self::Variable { name } => name,
@@ -112,13 +190,20 @@ impl Display for Expr {
             Self::Assign { name, .. } => write!(f, "assign {}", name.lexeme),
             Self::Binary { operator, .. } => write!(f, "{}", operator.lexeme),
             Self::Call { .. } => write!(f, "call"),
+            Self::Conditional { .. } => write!(f, "?:"),
             Self::Get { .. } | Self::Set { .. } => write!(f, "."),
             Self::Grouping { .. } => write!(f, "grouping"),
+            Self::Index { .. } | Self::SetIndex { .. } => write!(f, "[]"),
+            Self::Lambda(_) => write!(f, "lambda"),
+            Self::List { .. } => write!(f, "list"),
+            Self::Map { .. } => write!(f, "map"),
             Self::Logical { operator, .. } => write!(f, "{}", operator.lexeme),
             Self::Literal { value } => write!(f, "{}", value),
             Expr::Variable { name } => write!(f, "{}", name),
             Expr::Super { .. } => write!(f, "super"),
-            Self::Unary { operator, .. } => write!(f, "{}", operator.lexeme),
+            Self::Unary { operator, .. } | Self::Update { operator, .. } => {
+                write!(f, "{}", operator.lexeme)
+            }
             Expr::This { .. } => write!(f, "this"),
         }
     }
@@ -0,0 +1,112 @@
+/// A layout to print, where groups are broken over several lines only when
+/// they don't fit on one.
+#[derive(Debug, Clone)]
+pub enum Doc {
+    Text(String),
+    /// A space, or a line break in a broken group.
+    Line,
+    /// Nothing, or a line break in a broken group.
+    SoftLine,
+    /// Always a line break, the groups around it are broken.
+    HardLine,
+    /// Indent the lines started inside by one level.
+    Indent(Vec<Doc>),
+    /// Print flat if it fits, break every `Line` directly inside otherwise.
+    Group(Vec<Doc>),
+    Concat(Vec<Doc>),
+}
+
+impl Doc {
+    pub fn text(text: impl Into<String>) -> Self {
+        Self::Text(text.into())
+    }
+
+    fn has_hard_line(&self) -> bool {
+        match self {
+            Self::HardLine => true,
+            Self::Text(text) => text.contains('\n'),
+            Self::Line | Self::SoftLine => false,
+            Self::Indent(docs) | Self::Group(docs) | Self::Concat(docs) => {
+                docs.iter().any(Doc::has_hard_line)
+            }
+        }
+    }
+
+    /// Lay the document out in `width` columns, indenting by `indent` spaces.
+    pub fn render(&self, width: usize, indent: usize) -> String {
+        let mut out = String::new();
+        let mut column = 0;
+        // what's left to print: indentation, flat or not, and the document
+        let mut stack = vec![(0, false, self)];
+
+        while let Some((level, flat, doc)) = stack.pop() {
+            match doc {
+                Self::Text(text) => {
+                    out.push_str(text);
+                    column = match text.rsplit_once('\n') {
+                        Some((_, last)) => last.chars().count(),
+                        None => column + text.chars().count(),
+                    };
+                }
+                Self::Line if flat => {
+                    out.push(' ');
+                    column += 1;
+                }
+                Self::SoftLine if flat => (),
+                Self::Line | Self::SoftLine | Self::HardLine => {
+                    // no indentation on blank lines
+                    let line = out.rfind('\n').map_or(0, |i| i + 1);
+                    if out[line..].bytes().all(|b| b == b' ') {
+                        out.truncate(line);
+                    }
+                    out.push('\n');
+                    out.push_str(&" ".repeat(level));
+                    column = level;
+                }
+                Self::Indent(docs) => {
+                    stack.extend(docs.iter().rev().map(|doc| (level + indent, flat, doc)));
+                }
+                Self::Concat(docs) => {
+                    stack.extend(docs.iter().rev().map(|doc| (level, flat, doc)));
+                }
+                Self::Group(docs) => {
+                    let flat = flat
+                        || (!doc.has_hard_line()
+                            && fits(width.saturating_sub(column), doc, &stack));
+                    stack.extend(docs.iter().rev().map(|doc| (level, flat, doc)));
+                }
+            }
+        }
+
+        out
+    }
+}
+
+/// Whether `group` printed flat, and what follows it up to the next line
+/// break, takes at most `width` columns.
+fn fits(width: usize, group: &Doc, rest: &[(usize, bool, &Doc)]) -> bool {
+    let mut width = isize::try_from(width).unwrap_or(isize::MAX);
+    let mut stack = vec![(true, group)];
+    let mut rest = rest.iter().rev();
+
+    while width >= 0 {
+        let Some((flat, doc)) = stack.pop().or_else(|| rest.next().map(|&(_, f, d)| (f, d))) else {
+            return true;
+        };
+
+        match doc {
+            Doc::Text(text) => match text.split_once('\n') {
+                Some((first, _)) => return width >= first.chars().count() as isize,
+                None => width -= text.chars().count() as isize,
+            },
+            Doc::Line if flat => width -= 1,
+            Doc::SoftLine if flat => (),
+            Doc::Line | Doc::SoftLine | Doc::HardLine => return true,
+            Doc::Indent(docs) | Doc::Group(docs) | Doc::Concat(docs) => {
+                stack.extend(docs.iter().rev().map(|doc| (flat, doc)));
+            }
+        }
+    }
+
+    false
+}
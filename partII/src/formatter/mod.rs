@@ -0,0 +1,545 @@
+mod doc;
+
+use std::cell::Cell;
+
+use crate::{
+    cst::{Leaf, Node, NodeKind, Tree},
+    error::Error,
+    parser::Parser,
+    scanner::Scanner,
+    token::{Token, TokenType},
+};
+
+use doc::Doc;
+
+/// Layout settings of [`format`].
+#[derive(Debug, Clone, Copy)]
+pub struct FormatOptions {
+    /// Spaces per level of indentation.
+    pub indent: usize,
+    /// Lines are broken to fit in this many columns, when they can be.
+    pub line_length: usize,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        Self {
+            indent: 4,
+            line_length: 80,
+        }
+    }
+}
+
+/// Reprint a script in the canonical style, keeping its comments and single
+/// blank lines between statements.
+pub fn format(source: &str, options: FormatOptions) -> Result<String, Error> {
+    let tokens = Scanner::new(source.to_string())
+        .with_trivia()
+        .scan_tokens()?;
+    let tree = Parser::new(tokens).parse_cst()?;
+
+    Ok(Formatter {
+        options,
+        skip_comments: Cell::new(0),
+    }
+    .script(&tree))
+}
+
+struct Formatter {
+    options: FormatOptions,
+    /// How many of the comments before the next token were already laid out,
+    /// with the statement it starts or at the end of the line before it.
+    skip_comments: Cell<usize>,
+}
+
+/// The comments before the first token of a statement.
+struct Comments<'a> {
+    /// On the line of the token before, they stay at the end of that line.
+    trailing: Vec<&'a str>,
+    /// On their own lines, and whether a blank line comes before them.
+    leading: Vec<(bool, &'a str)>,
+    /// A blank line comes before the statement.
+    blank: bool,
+}
+
+impl<'a> Comments<'a> {
+    fn new(trivia: &'a [Token], after_token: bool) -> Self {
+        let mut comments = Self {
+            trailing: Vec::new(),
+            leading: Vec::new(),
+            blank: false,
+        };
+        // nothing before the start of a file to stay on the line of
+        let mut newlines = if after_token { 0 } else { 1 };
+
+        for token in trivia {
+            if token.ty == TokenType::Whitespace {
+                newlines += token.lexeme.matches('\n').count();
+                continue;
+            }
+
+            let text = &token.lexeme;
+            if newlines == 0 {
+                comments.trailing.push(text);
+            } else {
+                comments.leading.push((newlines > 1, text));
+            }
+            newlines = 0;
+        }
+
+        comments.blank = newlines > 1;
+        comments
+    }
+
+    fn is_empty(&self) -> bool {
+        self.trailing.is_empty() && self.leading.is_empty()
+    }
+}
+
+impl Formatter {
+    fn script(&self, tree: &Tree) -> String {
+        let Some((Node::Leaf(eof), stmts)) = tree.children.split_last() else {
+            unreachable!("a script ends with the end of file")
+        };
+        let (_, body) = self.statements(stmts, eof, false, Doc::HardLine);
+
+        let script = Doc::Concat(body).render(self.options.line_length, self.options.indent);
+        let script = script.trim_start_matches('\n');
+        if script.is_empty() {
+            String::new()
+        } else {
+            format!("{script}\n")
+        }
+    }
+
+    /// Lay out statements one per line, each after `separator`, with the
+    /// comments around them.
+    ///
+    /// `end` is the `}` or end of file after the statements, its comments go
+    /// last. The comments on the line of the token before the statements are
+    /// returned apart.
+    fn statements(
+        &self,
+        stmts: &[Node],
+        end: &Leaf,
+        after_token: bool,
+        separator: Doc,
+    ) -> (Vec<Doc>, Vec<Doc>) {
+        let mut head = Vec::new();
+        let mut body = Vec::new();
+
+        for (i, stmt) in stmts.iter().map(Some).chain([None]).enumerate() {
+            let leaf = stmt.map_or(end, first_leaf);
+            let comments = Comments::new(&leaf.trivia, after_token || i > 0);
+            let laid_out = comments.trailing.len() + comments.leading.len();
+
+            let line = if body.is_empty() {
+                &mut head
+            } else {
+                &mut body
+            };
+            for comment in comments.trailing {
+                line.push(Doc::text(" "));
+                line.push(Doc::text(comment));
+            }
+            for (blank, comment) in comments.leading {
+                if blank && !body.is_empty() {
+                    body.push(Doc::HardLine);
+                }
+                body.push(Doc::HardLine);
+                body.push(Doc::text(comment));
+            }
+
+            if let Some(stmt) = stmt {
+                if comments.blank && !body.is_empty() {
+                    body.push(Doc::HardLine);
+                }
+                body.push(separator.clone());
+                self.skip_comments.set(laid_out);
+                body.push(self.node(stmt));
+            }
+        }
+
+        (head, body)
+    }
+
+    /// Lay out a block, over several lines unless it is `inline` and fits on
+    /// one.
+    fn block(&self, tree: &Tree, inline: bool) -> Doc {
+        let [open, stmts @ .., Node::Leaf(close)] = &tree.children[..] else {
+            unreachable!("a block is between braces")
+        };
+
+        let has_comments = stmts
+            .iter()
+            .enumerate()
+            .map(|(i, stmt)| (i, first_leaf(stmt)))
+            .chain([(stmts.len(), close)])
+            .any(|(i, leaf)| !Comments::new(&leaf.trivia, i > 0).is_empty());
+        let separator = if inline && !has_comments {
+            Doc::Line
+        } else {
+            Doc::HardLine
+        };
+
+        let open = self.node(open);
+        let (head, body) = self.statements(stmts, close, true, separator.clone());
+        if head.is_empty() && body.is_empty() {
+            return Doc::Concat(vec![open, Doc::text("}")]);
+        }
+
+        let mut docs = vec![open];
+        docs.extend(head);
+        docs.push(Doc::Indent(body));
+        docs.push(separator);
+        docs.push(Doc::text("}"));
+        Doc::Group(docs)
+    }
+
+    fn node(&self, node: &Node) -> Doc {
+        match node {
+            Node::Leaf(leaf) => self.leaf(leaf),
+            Node::Tree(tree) => self.tree(tree),
+        }
+    }
+
+    fn leaf(&self, leaf: &Leaf) -> Doc {
+        let attached = matches!(leaf.token.ty, TokenType::Comma | TokenType::Semicolon);
+        let mut docs = self.comments(leaf, attached);
+        docs.push(Doc::text(&leaf.token.lexeme));
+        Doc::Concat(docs)
+    }
+
+    /// The comments before a token, in the middle of a statement: block
+    /// comments stay on the line, line comments end it. When the token is
+    /// `attached` to the previous one, like `,`, the comments stick to that
+    /// one instead: `a /* c */, b`.
+    fn comments(&self, leaf: &Leaf, attached: bool) -> Vec<Doc> {
+        let skip = self.skip_comments.replace(0);
+
+        let mut docs = Vec::new();
+        let comments = leaf
+            .trivia
+            .iter()
+            .filter(|token| token.ty != TokenType::Whitespace);
+        for token in comments.skip(skip) {
+            let text = &token.lexeme;
+            if attached {
+                docs.push(Doc::text(" "));
+            }
+            docs.push(Doc::text(text));
+            if text.starts_with("//") {
+                docs.push(Doc::HardLine);
+            } else if !attached {
+                docs.push(Doc::text(" "));
+            }
+        }
+        docs
+    }
+
+    /// `line`, after the comments on the line of the token before `leaf`. They
+    /// stay at the end of that line, and a line comment makes it a hard line.
+    fn end_of_line(&self, leaf: &Leaf, line: Doc) -> Vec<Doc> {
+        let trailing = Comments::new(&leaf.trivia, true).trailing;
+        self.skip_comments.set(trailing.len());
+
+        let mut docs = Vec::new();
+        let mut line = line;
+        for comment in trailing {
+            docs.push(Doc::text(" "));
+            docs.push(Doc::text(comment));
+            if comment.starts_with("//") {
+                line = Doc::HardLine;
+            }
+        }
+        docs.push(line);
+        docs
+    }
+
+    /// `line` then `node`, keeping the comments on the line before `node` at
+    /// the end of that line.
+    fn line_then(&self, line: Doc, node: &Node) -> Doc {
+        let mut docs = self.end_of_line(first_leaf(node), line);
+        docs.push(self.node(node));
+        Doc::Concat(docs)
+    }
+
+    fn tree(&self, tree: &Tree) -> Doc {
+        let children = &tree.children[..];
+        let node = |i: usize| self.node(&children[i]);
+        let space = || Doc::text(" ");
+
+        match tree.kind {
+            NodeKind::Script => unreachable!("a script is only at the root"),
+            NodeKind::Block => self.block(tree, false),
+            NodeKind::Break | NodeKind::Continue | NodeKind::Expression => {
+                Doc::Concat(children.iter().map(|child| self.node(child)).collect())
+            }
+            NodeKind::Class => {
+                let body = children
+                    .iter()
+                    .position(|child| token(child).map(|t| &t.ty) == Some(&TokenType::LeftBrace))
+                    .expect("a class has a body");
+
+                let mut docs = Vec::new();
+                for i in 0..body {
+                    docs.push(node(i));
+                    docs.push(space());
+                }
+
+                let [methods @ .., Node::Leaf(close)] = &children[body + 1..] else {
+                    unreachable!("a class body is between braces")
+                };
+                docs.push(node(body));
+                let (head, methods) = self.statements(methods, close, true, Doc::HardLine);
+                if head.is_empty() && methods.is_empty() {
+                    docs.push(Doc::text("}"));
+                } else {
+                    docs.extend(head);
+                    docs.push(Doc::Indent(methods));
+                    docs.push(Doc::HardLine);
+                    docs.push(Doc::text("}"));
+                }
+                Doc::Concat(docs)
+            }
+            NodeKind::For => {
+                let mut docs = vec![node(0), space(), node(1), node(2)];
+                let mut i = 3;
+                // the condition, then the increment, each optional
+                for _ in 0..2 {
+                    if let Node::Tree(clause) = &children[i] {
+                        docs.push(space());
+                        docs.push(self.tree(clause));
+                        i += 1;
+                    }
+                    docs.push(node(i));
+                    i += 1;
+                }
+                docs.push(self.body(&children[i]));
+                Doc::Concat(docs)
+            }
+            NodeKind::Function => {
+                let mut docs = Vec::new();
+                let mut i = 0;
+                if token(&children[0]).map(|t| &t.ty) == Some(&TokenType::Fun) {
+                    docs.push(node(0));
+                    docs.push(space());
+                    i = 1;
+                }
+                docs.push(node(i));
+                docs.push(self.function_rest(&children[i + 1..], false));
+                Doc::Concat(docs)
+            }
+            NodeKind::If => {
+                let mut docs = vec![node(0), space(), node(1), node(2), node(3)];
+                docs.push(self.body(&children[4]));
+
+                if children.len() > 5 {
+                    docs.push(if is_block(&children[4]) {
+                        space()
+                    } else {
+                        Doc::HardLine
+                    });
+                    docs.push(node(5));
+                    docs.push(match &children[6] {
+                        Node::Tree(tree) if tree.kind == NodeKind::If => {
+                            Doc::Concat(vec![space(), self.tree(tree)])
+                        }
+                        other => self.body(other),
+                    });
+                }
+                Doc::Concat(docs)
+            }
+            NodeKind::Print => Doc::Concat(vec![node(0), space(), node(1), node(2)]),
+            NodeKind::Return if children.len() == 3 => {
+                Doc::Concat(vec![node(0), space(), node(1), node(2)])
+            }
+            NodeKind::Return => Doc::Concat(vec![node(0), node(1)]),
+            NodeKind::Var => {
+                let mut docs = vec![node(0), space(), node(1)];
+                if children.len() == 5 {
+                    docs.extend([space(), node(2), space(), node(3)]);
+                }
+                docs.push(node(children.len() - 1));
+                Doc::Concat(docs)
+            }
+            NodeKind::While => Doc::Concat(vec![
+                node(0),
+                space(),
+                node(1),
+                node(2),
+                node(3),
+                self.body(&children[4]),
+            ]),
+
+            NodeKind::Assign => Doc::Concat(vec![node(0), space(), node(1), space(), node(2)]),
+            NodeKind::Binary if token(&children[1]).map(|t| &t.ty) == Some(&TokenType::Comma) => {
+                Doc::Group(vec![
+                    node(0),
+                    node(1),
+                    self.line_then(Doc::Line, &children[2]),
+                ])
+            }
+            NodeKind::Binary | NodeKind::Logical => Doc::Group(vec![
+                node(0),
+                space(),
+                node(1),
+                Doc::Indent(vec![self.line_then(Doc::Line, &children[2])]),
+            ]),
+            NodeKind::Call => Doc::Concat(vec![node(0), self.delimited(&children[1..])]),
+            NodeKind::Conditional => Doc::Group(vec![
+                node(0),
+                Doc::Indent(vec![
+                    self.line_then(Doc::Line, &children[1]),
+                    space(),
+                    node(2),
+                    self.line_then(Doc::Line, &children[3]),
+                    space(),
+                    node(4),
+                ]),
+            ]),
+            NodeKind::Get
+            | NodeKind::Grouping
+            | NodeKind::Index
+            | NodeKind::Literal
+            | NodeKind::Postfix
+            | NodeKind::Super
+            | NodeKind::This
+            | NodeKind::Variable => {
+                Doc::Concat(children.iter().map(|child| self.node(child)).collect())
+            }
+            NodeKind::Interpolation => {
+                // the interpolated expressions can't be broken inside the string
+                let docs = children
+                    .iter()
+                    .map(|child| match child {
+                        Node::Leaf(leaf) => self.leaf(leaf),
+                        Node::Tree(tree) => {
+                            Doc::text(self.tree(tree).render(usize::MAX, self.options.indent))
+                        }
+                    })
+                    .collect();
+                Doc::Concat(docs)
+            }
+            NodeKind::Lambda => Doc::Concat(vec![
+                node(0),
+                space(),
+                self.function_rest(&children[1..], true),
+            ]),
+            NodeKind::List | NodeKind::Map => self.delimited(children),
+            NodeKind::Unary => {
+                // `- -x` isn't `--x`, but `!!x` is `!!x`
+                let operator = &token(&children[0]).expect("an operator").lexeme;
+                let operand = &first_leaf(&children[1]).token.lexeme;
+                let mut docs = vec![node(0)];
+                if glues(operator, operand) {
+                    docs.push(space());
+                }
+                docs.push(node(1));
+                Doc::Concat(docs)
+            }
+        }
+    }
+
+    /// The parameters and body of a function, from its `(`. The body of a
+    /// lambda stays on one line if it fits.
+    fn function_rest(&self, children: &[Node], lambda: bool) -> Doc {
+        let [parameters @ .., Node::Tree(body)] = children else {
+            unreachable!("a function ends with its body")
+        };
+        Doc::Concat(vec![
+            self.delimited(parameters),
+            Doc::text(" "),
+            self.block(body, lambda),
+        ])
+    }
+
+    /// The body of a control flow statement, moved to the next line if it
+    /// isn't a block and doesn't fit.
+    fn body(&self, node: &Node) -> Doc {
+        if is_block(node) {
+            Doc::Concat(vec![Doc::text(" "), self.node(node)])
+        } else {
+            Doc::Group(vec![Doc::Indent(vec![self.line_then(Doc::Line, node)])])
+        }
+    }
+
+    /// Items separated by commas between brackets, `(a, b)`, `[a, b]` or
+    /// `{k: v}`, one item per line when they don't fit on one.
+    fn delimited(&self, children: &[Node]) -> Doc {
+        let [open, items @ .., close] = children else {
+            unreachable!("delimited items are between brackets")
+        };
+
+        let open = self.node(open);
+        if items.is_empty() {
+            return Doc::Concat(vec![open, self.node(close)]);
+        }
+
+        let mut docs = self.end_of_line(first_leaf(&items[0]), Doc::SoftLine);
+        for (i, item) in items.iter().enumerate() {
+            let Some(ty) = token(item).map(|t| &t.ty) else {
+                docs.push(self.node(item));
+                continue;
+            };
+
+            match ty {
+                // the trailing comma goes, not its comments
+                TokenType::Comma if i == items.len() - 1 => {
+                    let Node::Leaf(leaf) = item else {
+                        unreachable!()
+                    };
+                    docs.extend(self.comments(leaf, true));
+                }
+                TokenType::Comma => {
+                    docs.push(self.node(item));
+                    docs.extend(self.end_of_line(first_leaf(&items[i + 1]), Doc::Line));
+                }
+                TokenType::Colon => {
+                    let Node::Leaf(leaf) = item else {
+                        unreachable!()
+                    };
+                    docs.extend(self.comments(leaf, true));
+                    docs.push(Doc::text(&leaf.token.lexeme));
+                    docs.push(Doc::text(" "));
+                }
+                _ => docs.push(self.node(item)),
+            }
+        }
+
+        let mut end = self.end_of_line(first_leaf(close), Doc::SoftLine);
+        let line = end.pop().expect("the line before the closing bracket");
+        docs.extend(end);
+        Doc::Group(vec![open, Doc::Indent(docs), line, self.node(close)])
+    }
+}
+
+/// Whether `left` followed by `right` without a space scans to other tokens,
+/// like `-` and `-x` making `--x`.
+fn glues(left: &str, right: &str) -> bool {
+    match Scanner::new(format!("{left}{right}")).scan_tokens() {
+        Ok(tokens) => tokens.first().map(|token| token.lexeme.as_str()) != Some(left),
+        // `left` is a whole token, the error is about the start of a string
+        // `right` comes from
+        Err(_) => false,
+    }
+}
+
+/// The token of a leaf.
+fn token(node: &Node) -> Option<&Token> {
+    match node {
+        Node::Leaf(leaf) => Some(&leaf.token),
+        Node::Tree(_) => None,
+    }
+}
+
+fn first_leaf(node: &Node) -> &Leaf {
+    match node {
+        Node::Leaf(leaf) => leaf,
+        Node::Tree(tree) => first_leaf(&tree.children[0]),
+    }
+}
+
+fn is_block(node: &Node) -> bool {
+    matches!(node, Node::Tree(tree) if tree.kind == NodeKind::Block)
+}
@@ -1,4 +1,4 @@
-use std::{collections::HashMap, fmt::Display, rc::Rc};
+use std::{cell::RefCell, collections::HashMap, fmt::Display, rc::Rc};
 
 use crate::{class::Class, error::RuntimeError, token::Token, value::Value};
 
@@ -7,8 +7,8 @@ use anyhow::anyhow;
 #[derive(Debug, Clone)]
 pub struct Instance {
     class: Class,
-    // we want to keep the same env between all calls on a same instance
-    fields: Rc<HashMap<String, Value>>,
+    // every reference to the same instance share its fields
+    fields: Rc<RefCell<HashMap<String, Value>>>,
 }
 
 impl Instance {
@@ -20,7 +20,7 @@ impl Instance {
     }
 
     pub fn get(&self, name: &Token) -> Result<Value, RuntimeError> {
-        if let Some(field) = self.fields.get(&name.lexeme) {
+        if let Some(field) = self.fields.borrow().get(&name.lexeme) {
             Ok(field.clone())
         } else if let Some(method) = self.class.find_method(&name.lexeme) {
             Ok(method.bind(self.clone()).into())
@@ -29,8 +29,18 @@ impl Instance {
         }
     }
 
-    pub fn set(&mut self, name: &Token, value: Value) {
-        unsafe { Rc::get_mut_unchecked(&mut self.fields) }.insert(name.lexeme.clone(), value);
+    /// Whether both are references to the same instance.
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.fields, &other.fields)
+    }
+
+    /// Address identifying the instance.
+    pub fn as_ptr(&self) -> *const () {
+        Rc::as_ptr(&self.fields).cast()
+    }
+
+    pub fn set(&self, name: &Token, value: Value) {
+        self.fields.borrow_mut().insert(name.lexeme.clone(), value);
     }
 }
 
@@ -1,12 +1,9 @@
-use std::{
-    collections::HashMap,
-    ops::{Deref, DerefMut},
-};
+use std::collections::HashMap;
 
 use crate::{
     callable::Callable,
     class::Class,
-    environment::Environment,
+    environment::{Environment, SharedEnvironment},
     error::RuntimeError,
     expr::Expr,
     native_functions,
@@ -15,18 +12,43 @@ use crate::{
     value::Value,
 };
 use anyhow::anyhow;
+use indexmap::IndexMap;
 
 type Result<T> = std::result::Result<T, RuntimeError>;
 
-#[derive(Debug, Clone, Default)]
+/// Why a statement stopped before reaching its end.
+///
+/// `return`, `break` and `continue` unwind the statements up to the function
+/// or the loop handling them, on the same path as the runtime errors.
+#[derive(Debug)]
+pub enum ControlFlow {
+    Return(Value),
+    Break,
+    Continue,
+    Error(RuntimeError),
+}
+
+impl From<RuntimeError> for ControlFlow {
+    fn from(error: RuntimeError) -> Self {
+        Self::Error(error)
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct Interpreter {
-    pub env: Environment,
+    pub globals: SharedEnvironment,
+    pub env: SharedEnvironment,
     pub locals: HashMap<*const Expr, usize>,
 }
 
 impl Interpreter {
     pub fn new() -> Self {
-        let mut interpreter = Self::default();
+        let globals = Environment::new().shared();
+        let interpreter = Self {
+            env: globals.clone(),
+            globals,
+            locals: HashMap::new(),
+        };
 
         interpreter.define(String::from("clock"), native_functions::Clock::value());
         interpreter.define(
@@ -37,67 +59,96 @@ impl Interpreter {
             String::from("parseInt"),
             native_functions::ParseInt::value(),
         );
+        interpreter.define(String::from("len"), native_functions::Len::value());
+        interpreter.define(String::from("push"), native_functions::Push::value());
+        interpreter.define(String::from("pop"), native_functions::Pop::value());
+        interpreter.define(String::from("keys"), native_functions::Keys::value());
+        interpreter.define(String::from("values"), native_functions::Values::value());
+        interpreter.define(String::from("has"), native_functions::Has::value());
+        interpreter.define(String::from("remove"), native_functions::Remove::value());
 
         interpreter
     }
 
     pub fn interpret(&mut self, stmts: &[Stmt]) -> std::result::Result<(), RuntimeError> {
         for stmt in stmts {
-            stmt.evaluate(self)?;
+            match stmt.evaluate(self) {
+                Ok(()) => (),
+                Err(ControlFlow::Error(error)) => return Err(error),
+                Err(flow) => unreachable!("The resolver let a {flow:?} escape to the top level"),
+            }
         }
         Ok(())
     }
 
-    fn lookup_variable(&mut self, name: &Token, expr: &Expr) -> Result<&Value> {
+    /// Run `stmts` in `env`, then come back to the current environment even
+    /// if one of the statements failed or left the block early.
+    pub fn execute_block(
+        &mut self,
+        stmts: &[Stmt],
+        env: Environment,
+    ) -> std::result::Result<(), ControlFlow> {
+        let previous = std::mem::replace(&mut self.env, env.shared());
+
+        let result = stmts.iter().try_for_each(|stmt| stmt.evaluate(self));
+
+        self.env = previous;
+        result
+    }
+
+    pub fn define(&self, name: impl AsRef<str>, value: impl Into<Value>) {
+        self.env.borrow_mut().define(name, value);
+    }
+
+    pub fn assign(&self, name: &Token, value: Value) -> Result<()> {
+        self.env.borrow_mut().assign(name, value)
+    }
+
+    pub fn get(&self, name: impl AsRef<str>) -> Result<Value> {
+        self.env.borrow().get(name)
+    }
+
+    fn lookup_variable(&self, name: &Token, expr: &Expr) -> Result<Value> {
         if let Some(distance) = self.locals.get(&(expr as *const Expr)) {
-            self.get_at(*distance, name)
+            self.env.borrow().get_at(*distance, name)
         } else {
-            self.globals().get(name)
+            self.globals.borrow().get(name)
         }
     }
 
-    pub fn resolve(&mut self, expr: &Expr, depth: usize) {
-        self.locals.insert(expr, depth);
+    fn assign_variable(&self, name: &Token, expr: &Expr, value: Value) -> Result<()> {
+        if let Some(distance) = self.locals.get(&(expr as *const Expr)) {
+            self.env.borrow_mut().assign_at(*distance, name, value)
+        } else {
+            self.globals.borrow_mut().assign(name, value)
+        }
     }
-}
 
-impl Deref for Interpreter {
-    type Target = Environment;
-
-    fn deref(&self) -> &Self::Target {
-        &self.env
+    pub fn resolve(&mut self, expr: &Expr, depth: usize) {
+        self.locals.insert(expr, depth);
     }
 }
 
-impl DerefMut for Interpreter {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.env
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
 impl Stmt {
-    pub fn evaluate(&self, interpreter: &mut Interpreter) -> Result<()> {
+    pub fn evaluate(&self, interpreter: &mut Interpreter) -> std::result::Result<(), ControlFlow> {
         match self {
             Stmt::Block(stmts) => {
-                let previous_env = std::mem::take(&mut interpreter.env);
-                interpreter.enclose(previous_env);
-
-                for stmt in stmts.iter() {
-                    match stmt.evaluate(interpreter) {
-                        Ok(_) => (),
-                        Err(e) => {
-                            interpreter.env =
-                                std::mem::take(&mut interpreter.env).destroy().unwrap();
-                            return Err(e);
-                        }
-                    }
-                }
-                interpreter.env = std::mem::take(&mut interpreter.env).destroy().unwrap();
+                let env = Environment::enclosed_by(interpreter.env.clone());
+                interpreter.execute_block(stmts, env)?;
             }
+            Stmt::Break(_) => return Err(ControlFlow::Break),
+            Stmt::Continue(_) => return Err(ControlFlow::Continue),
             Stmt::Class {
                 name,
                 superclass,
                 methods,
+                ..
             } => {
                 let superclass = superclass
                     .as_ref()
@@ -105,26 +156,30 @@ impl Stmt {
                         let superclass = superclass.evaluate(interpreter)?;
                         match superclass {
                             Value::Class(class) => Ok(class),
-                            _ => Err(anyhow!("Superclass must be a class"))?,
+                            _ => Err(RuntimeError::from(anyhow!("Superclass must be a class"))
+                                .at(Some(name)))?,
                         }
                     })
                     .transpose()?;
                 interpreter.define(name.lexeme.clone(), Value::Nil);
 
-                if let Some(_) = superclass {
-                    interpreter.env.enclosed_by(Environment::new());
-                    interpreter.define("super", superclass.clone().unwrap());
+                // the methods are closures over an environment holding `super`
+                let mut closure = interpreter.env.clone();
+                if let Some(ref superclass) = superclass {
+                    let mut env = Environment::enclosed_by(closure);
+                    env.define("super", superclass.clone());
+                    closure = env.shared();
                 }
 
                 let methods = methods
                     .iter()
-                    .map(|method| (method.name.lexeme.clone(), method.clone()))
+                    .map(|method| {
+                        let method = method.clone().with_environment(closure.clone());
+                        (method.name.lexeme.clone(), method)
+                    })
                     .collect();
 
-                let class = Class::new(name.lexeme.clone(), methods, superclass.clone());
-                if let Some(_) = superclass {
-                    interpreter.declose();
-                }
+                let class = Class::new(name.lexeme.clone(), methods, superclass);
                 interpreter.assign(name, class.into())?;
             }
             Stmt::Expression(expr) => drop(expr.evaluate(interpreter)?),
@@ -140,17 +195,28 @@ impl Stmt {
                     else_branch.evaluate(interpreter)?;
                 }
             }
-            Stmt::Print(expr) => println!("{}", expr.evaluate(interpreter)?),
+            Stmt::Print { value, .. } => println!("{}", value.evaluate(interpreter)?),
             Stmt::Return { value, .. } => {
                 let value = value
                     .as_ref()
                     .unwrap_or(&Expr::default())
                     .evaluate(interpreter)?;
-                return Err(RuntimeError::Return(value));
+                return Err(ControlFlow::Return(value));
             }
-            Stmt::While { condition, body } => {
+            Stmt::While {
+                condition,
+                body,
+                increment,
+            } => {
                 while condition.evaluate(interpreter)?.is_truthy() {
-                    body.evaluate(interpreter)?;
+                    match body.evaluate(interpreter) {
+                        Ok(()) | Err(ControlFlow::Continue) => (),
+                        Err(ControlFlow::Break) => break,
+                        Err(flow) => return Err(flow),
+                    }
+                    if let Some(increment) = increment {
+                        increment.evaluate(interpreter)?;
+                    }
                 }
             }
             Stmt::Var { name, initializer } => {
@@ -167,10 +233,15 @@ impl Stmt {
 
 impl Expr {
     pub fn evaluate(&self, interpreter: &mut Interpreter) -> Result<Value> {
+        self.evaluate_inner(interpreter)
+            .map_err(|error| error.at(self.token()))
+    }
+
+    fn evaluate_inner(&self, interpreter: &mut Interpreter) -> Result<Value> {
         match self {
             Expr::Assign { name, value } => {
                 let value = value.evaluate(interpreter)?;
-                interpreter.assign(&name, value.clone())?;
+                interpreter.assign_variable(name, self, value.clone())?;
                 Ok(value)
             }
             Expr::Binary {
@@ -179,42 +250,23 @@ impl Expr {
                 right,
             } => {
                 let (left, right) = (left.evaluate(interpreter)?, right.evaluate(interpreter)?);
-
-                match operator.ty {
-                    TokenType::Slash => Ok((left.number()? / right.number()?).into()),
-                    TokenType::Star => Ok((left.number()? * right.number()?).into()),
-                    TokenType::Minus => Ok((left.number()? - right.number()?).into()),
-                    TokenType::Plus if left.is_string() || right.is_string() => {
-                        Ok((left.to_string() + &right.to_string()).into())
-                    }
-                    TokenType::Plus if left.is_number() => {
-                        Ok((left.number()? + right.number()?).into())
-                    }
-                    TokenType::Plus => Err(anyhow!(
-                        "Operator `+` can only be applied to `string` or `number`"
-                    ))?,
-                    TokenType::Greater => Ok((left.number()? > right.number()?).into()),
-                    TokenType::GreaterEqual => Ok((left.number()? >= right.number()?).into()),
-                    TokenType::Less => Ok((left.number()? < right.number()?).into()),
-                    TokenType::LessEqual => Ok((left.number()? <= right.number()?).into()),
-                    TokenType::BangEqual => Ok((left == right).into()),
-                    TokenType::EqualEqual => Ok((left == right).into()),
-                    _ => unreachable!(),
-                }
+                binary(&operator.ty, left, right)
             }
             Expr::Call {
                 callee,
-                paren: _,
+                paren,
                 arguments,
             } => {
-                let mut callee = callee.evaluate(interpreter)?;
+                let callee = callee.evaluate(interpreter)?;
 
                 let arguments = arguments
-                    .into_iter()
+                    .iter()
                     .map(|arg| arg.evaluate(interpreter))
                     .collect::<Result<Vec<_>>>()?;
 
-                callee.call(interpreter, arguments)
+                callee
+                    .call(interpreter, arguments)
+                    .map_err(|error| error.called_from(callee.name(), paren))
             }
             Expr::Get { object, name } => {
                 let object = object.evaluate(interpreter)?;
@@ -223,7 +275,41 @@ impl Expr {
                     _ => Err(anyhow!("Only object have properties."))?,
                 }
             }
+            Expr::Conditional {
+                condition,
+                then_branch,
+                else_branch,
+                ..
+            } => {
+                if condition.evaluate(interpreter)?.is_truthy() {
+                    then_branch.evaluate(interpreter)
+                } else {
+                    else_branch.evaluate(interpreter)
+                }
+            }
             Expr::Grouping { expression } => expression.evaluate(interpreter),
+            Expr::Index { object, index, .. } => {
+                let object = object.evaluate(interpreter)?;
+                let index = index.evaluate(interpreter)?;
+                object.get_index(&index)
+            }
+            Expr::List { elements, .. } => Ok(elements
+                .iter()
+                .map(|element| element.evaluate(interpreter))
+                .collect::<Result<Vec<_>>>()?
+                .into()),
+            Expr::Lambda(function) => Ok(function
+                .clone()
+                .with_environment(interpreter.env.clone())
+                .to_value()),
+            Expr::Map { entries, .. } => Ok(entries
+                .iter()
+                .map(|(key, value)| {
+                    let key = key.evaluate(interpreter)?.key()?;
+                    Ok((key, value.evaluate(interpreter)?))
+                })
+                .collect::<Result<IndexMap<_, _>>>()?
+                .into()),
             Expr::Literal { value } => Ok(value.clone()),
             Expr::Logical {
                 left,
@@ -251,7 +337,7 @@ impl Expr {
             } => {
                 let object = object.evaluate(interpreter)?;
                 match object {
-                    Value::Instance(mut object) => {
+                    Value::Instance(object) => {
                         let value = value.evaluate(interpreter)?;
                         object.set(name, value.clone());
                         Ok(value)
@@ -259,34 +345,130 @@ impl Expr {
                     _ => Err(anyhow!("Only instances have fields."))?,
                 }
             }
+            Expr::SetIndex {
+                object,
+                index,
+                value,
+                ..
+            } => {
+                let object = object.evaluate(interpreter)?;
+                let index = index.evaluate(interpreter)?;
+                let value = value.evaluate(interpreter)?;
+                object.set_index(index, value.clone())?;
+                Ok(value)
+            }
             Expr::Super { keyword, method } => {
-                println!("HERE");
-                let distance = interpreter.locals.get(&(self as *const Expr)).unwrap();
-                println!("distance: {}", distance);
-                println!("{:#?}", interpreter.env);
-                // let superclass = interpreter.get_at(*distance, keyword)?.clone().class()?;
-                let superclass = interpreter.get(keyword)?.clone().class()?;
-                println!("HERE2");
+                let distance = interpreter.locals[&(self as *const Expr)];
+                let superclass = interpreter.lookup_variable(keyword, self)?.class()?;
                 let object = interpreter
                     .env
-                    .get_at(*distance - 1, "this")?
-                    .clone()
+                    .borrow()
+                    .get_at(distance - 1, "this")?
                     .instance()?;
 
-                println!("HOARE");
                 if let Some(method) = superclass.find_method(method) {
                     Ok(method.bind(object).into())
                 } else {
                     Err(anyhow!("Can't use `super` in a class with no superclass"))?
                 }
             }
-            Expr::This { keyword } => Ok(interpreter.lookup_variable(keyword, self)?.clone()),
+            Expr::This { keyword } => interpreter.lookup_variable(keyword, self),
             Expr::Unary { operator, right } => match operator.ty {
                 TokenType::Bang => Ok((right.evaluate(interpreter)?.is_falsy()).into()),
                 TokenType::Minus => right.evaluate(interpreter)?.map_number(|n| -n),
                 _ => unreachable!(),
             },
-            Expr::Variable { name } => Ok(interpreter.lookup_variable(name, self)?.clone()),
+            Expr::Update {
+                target,
+                operator,
+                value,
+                postfix,
+            } => {
+                let binary_operator = operator
+                    .ty
+                    .compound_operator()
+                    .expect("The parser only builds updates from compound operators");
+                // compute the new value, and what the update evaluates to
+                let update = |old: Value, interpreter: &mut Interpreter| -> Result<_> {
+                    if matches!(operator.ty, TokenType::PlusPlus | TokenType::MinusMinus) {
+                        old.clone().number()?;
+                    }
+                    let new = binary(&binary_operator, old.clone(), value.evaluate(interpreter)?)?;
+                    Ok(if *postfix {
+                        (new, old)
+                    } else {
+                        (new.clone(), new)
+                    })
+                };
+
+                // the object of the target is only evaluated once
+                match target.as_ref() {
+                    Expr::Variable { name } => {
+                        let old = interpreter.lookup_variable(name, target)?;
+                        let (new, result) = update(old, interpreter)?;
+                        interpreter.assign_variable(name, target, new)?;
+                        Ok(result)
+                    }
+                    Expr::Get { object, name } => {
+                        let Value::Instance(object) = object.evaluate(interpreter)? else {
+                            Err(anyhow!("Only instances have fields."))?
+                        };
+                        let (new, result) = update(object.get(name)?, interpreter)?;
+                        object.set(name, new);
+                        Ok(result)
+                    }
+                    Expr::Index { object, index, .. } => {
+                        let object = object.evaluate(interpreter)?;
+                        let index = index.evaluate(interpreter)?;
+                        let (new, result) = update(object.get_index(&index)?, interpreter)?;
+                        object.set_index(index, new)?;
+                        Ok(result)
+                    }
+                    _ => unreachable!("The parser only builds updates of assignable targets"),
+                }
+            }
+            Expr::Variable { name } => interpreter.lookup_variable(name, self),
+        }
+    }
+}
+
+/// Apply a binary operator, both operands being already evaluated.
+fn binary(operator: &TokenType, left: Value, right: Value) -> Result<Value> {
+    match operator {
+        TokenType::Slash => Ok((left.number()? / right.number()?).into()),
+        TokenType::Star => Ok((left.number()? * right.number()?).into()),
+        TokenType::StarStar => Ok(left.number()?.powf(right.number()?).into()),
+        TokenType::Percent | TokenType::TildeSlash => {
+            let (left, right) = (left.number()?, right.number()?);
+            if right == 0.0 {
+                Err(anyhow!("Division by zero."))?
+            } else if *operator == TokenType::Percent {
+                Ok((left % right).into())
+            } else {
+                // truncate, so that `a == (a ~/ b) * b + a % b`
+                Ok((left / right).trunc().into())
+            }
+        }
+        TokenType::Minus => Ok((left.number()? - right.number()?).into()),
+        TokenType::Plus if left.is_list() && right.is_list() => {
+            let mut list = left.list()?.borrow().clone();
+            list.extend(right.list()?.borrow().iter().cloned());
+            Ok(list.into())
+        }
+        TokenType::Plus if left.is_string() || right.is_string() => {
+            Ok((left.to_string() + &right.to_string()).into())
         }
+        TokenType::Plus if left.is_number() => Ok((left.number()? + right.number()?).into()),
+        TokenType::Plus => Err(anyhow!(
+            "Operator `+` can only be applied to `string`, `number` or `list`"
+        ))?,
+        TokenType::Greater => Ok((left.number()? > right.number()?).into()),
+        TokenType::GreaterEqual => Ok((left.number()? >= right.number()?).into()),
+        TokenType::Less => Ok((left.number()? < right.number()?).into()),
+        TokenType::LessEqual => Ok((left.number()? <= right.number()?).into()),
+        TokenType::BangEqual => Ok((left != right).into()),
+        TokenType::EqualEqual => Ok((left == right).into()),
+        TokenType::Comma => Ok(right),
+        _ => unreachable!(),
     }
 }
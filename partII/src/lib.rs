@@ -0,0 +1,19 @@
+#![allow(dead_code)]
+
+pub mod ast_printer;
+pub mod callable;
+pub mod class;
+pub mod cst;
+pub mod environment;
+pub mod error;
+pub mod expr;
+pub mod formatter;
+pub mod instance;
+pub mod interpreter;
+pub mod native_functions;
+pub mod parser;
+pub mod resolver;
+pub mod scanner;
+pub mod stmt;
+pub mod token;
+pub mod value;
@@ -1,61 +1,129 @@
-#![feature(get_mut_unchecked)]
-#![allow(dead_code)]
 #![allow(non_snake_case)]
 
-mod ast_printer;
-mod callable;
-mod class;
-mod environment;
-mod error;
-mod expr;
-mod instance;
-mod interpreter;
-mod native_functions;
-mod parser;
-mod resolver;
-mod scanner;
-mod stmt;
-mod token;
-mod value;
-
 use std::{
     io::{BufRead, Write},
     path::Path,
 };
 
-use interpreter::Interpreter;
-use resolver::Resolver;
-use scanner::Scanner;
-
-use crate::error::{Result, SetupError};
-use crate::parser::Parser;
+use lox::error::{Result, SetupError, Source};
+use lox::formatter::{self, FormatOptions};
+use lox::interpreter::Interpreter;
+use lox::parser::Parser;
+use lox::resolver::Resolver;
+use lox::scanner::Scanner;
+
+/// Options given on the command line.
+#[derive(Debug, Default, Clone, Copy)]
+struct Options {
+    /// Refuse to run scripts with warnings, useful for CI.
+    deny_warnings: bool,
+}
 
 fn main() -> Result<()> {
-    let args: Vec<_> = std::env::args().collect();
+    if std::env::args().nth(1).is_some_and(|arg| arg == "fmt") {
+        return run_fmt(std::env::args().skip(2));
+    }
+
+    let mut options = Options::default();
+    let mut args = Vec::new();
+
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "--deny-warnings" => options.deny_warnings = true,
+            _ => args.push(arg),
+        }
+    }
 
-    if args.len() > 3 {
-        return Err(SetupError::Usage)?;
+    if args.len() > 1 {
+        Err(SetupError::Usage)?;
     }
 
-    if let Some(filename) = args.get(1) {
-        run_file(filename)
+    if let Some(filename) = args.first() {
+        run_file(filename, options)
     } else if atty::is(atty::Stream::Stdin) {
-        run_prompt()
+        run_prompt(options)
     } else {
-        run_file("/dev/stdin")
+        run_file("/dev/stdin", options)
     }
 }
 
-fn run_file(filename: impl AsRef<Path>) -> Result<()> {
+fn run_file(filename: impl AsRef<Path>, options: Options) -> Result<()> {
+    let filename = filename.as_ref();
     let file = std::fs::read_to_string(filename).map_err(SetupError::from)?;
+    let name = filename.display().to_string();
+    let source = Source::new(&name, &file);
     let mut interpreter = Interpreter::new();
 
-    run(file, &mut interpreter)?;
+    if let Err(error) = run(&source, &mut interpreter, options) {
+        eprint!("{}", error.report(&source));
+        std::process::exit(error.exit_code());
+    }
+
+    Ok(())
+}
+
+/// Reformat the files in place, or stdin to stdout. With `--check` nothing is
+/// written, and the exit code is 1 if a file isn't formatted.
+fn run_fmt(args: impl Iterator<Item = String>) -> Result<()> {
+    let mut options = FormatOptions::default();
+    let mut check = false;
+    let mut files = Vec::new();
+
+    let mut args = args.peekable();
+    while let Some(arg) = args.next() {
+        let mut width = || {
+            args.next()
+                .and_then(|width| width.parse().ok())
+                .ok_or(SetupError::FmtUsage)
+        };
+        match arg.as_str() {
+            "--check" => check = true,
+            "--indent" => options.indent = width()?,
+            "--line-length" => options.line_length = width()?,
+            _ if arg.starts_with("--") => Err(SetupError::FmtUsage)?,
+            _ => files.push(arg),
+        }
+    }
+
+    let stdin = files.is_empty();
+    if stdin {
+        files.push(String::from("/dev/stdin"));
+    }
+
+    let mut unformatted = false;
+    for filename in &files {
+        let file = std::fs::read_to_string(filename).map_err(SetupError::from)?;
+        let name = if stdin { "<stdin>" } else { filename };
+        let source = Source::new(name, &file);
+
+        let formatted = match formatter::format(&file, options) {
+            Ok(formatted) => formatted,
+            Err(error) => {
+                eprint!("{}", error.report(&source));
+                std::process::exit(error.exit_code());
+            }
+        };
+
+        if check {
+            if formatted != file {
+                eprintln!("{name} is not formatted");
+                unformatted = true;
+            }
+        } else if stdin {
+            print!("{formatted}");
+        } else if formatted != file {
+            std::fs::write(filename, formatted).map_err(SetupError::from)?;
+        }
+    }
+
+    if unformatted {
+        std::process::exit(1);
+    }
 
     Ok(())
 }
 
-fn run_prompt() -> Result<()> {
+fn run_prompt(options: Options) -> Result<()> {
     let stdin = std::io::stdin();
     let stdin = stdin.lock();
     let mut stdout = std::io::stdout();
@@ -66,10 +134,11 @@ fn run_prompt() -> Result<()> {
     stdout.flush().map_err(SetupError::from)?;
 
     for line in stdin.lines() {
-        let line = line.map_err(SetupError::from);
-        match run(line?, &mut interpreter) {
+        let line = line.map_err(SetupError::from)?;
+        let source = Source::new("<stdin>", &line);
+        match run(&source, &mut interpreter, options) {
             Ok(_) => (),
-            Err(error) => println!("{}", error),
+            Err(error) => print!("{}", error.report(&source)),
         }
         print!("> ");
         stdout.flush().map_err(SetupError::from)?;
@@ -78,8 +147,8 @@ fn run_prompt() -> Result<()> {
     Ok(())
 }
 
-fn run(input: String, interpreter: &mut Interpreter) -> Result<()> {
-    let scanner = Scanner::new(input);
+fn run(source: &Source, interpreter: &mut Interpreter, options: Options) -> Result<()> {
+    let scanner = Scanner::new(source.code.to_string());
     let tokens = scanner.scan_tokens()?;
     let parser = Parser::new(tokens);
     let stmts = parser.parse()?;
@@ -87,5 +156,11 @@ fn run(input: String, interpreter: &mut Interpreter) -> Result<()> {
     let mut resolver = Resolver::new(interpreter);
     resolver.resolve(&stmts)?;
 
+    let warnings = resolver.warnings();
+    if options.deny_warnings && !warnings.is_empty() {
+        return Err(warnings.into());
+    }
+    eprint!("{}", warnings.report(source, "warning"));
+
     Ok(interpreter.interpret(&stmts)?)
 }
@@ -19,7 +19,7 @@ impl Clock {
 
 impl Callable for Clock {
     fn call(
-        &mut self,
+        &self,
         _interpreter: &mut Interpreter,
         arguments: Vec<Value>,
     ) -> Result<Value, RuntimeError> {
@@ -38,4 +38,8 @@ impl Callable for Clock {
     fn arity(&self) -> usize {
         0
     }
+
+    fn name(&self) -> &str {
+        "clock"
+    }
 }
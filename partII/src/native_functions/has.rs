@@ -0,0 +1,40 @@
+use std::rc::Rc;
+
+use anyhow::anyhow;
+
+use crate::{callable::Callable, error::RuntimeError, interpreter::Interpreter, value::Value};
+
+#[derive(Debug)]
+pub struct Has {}
+
+impl Has {
+    pub fn value() -> Value {
+        let has = Rc::new(Self {}) as Rc<dyn Callable>;
+        has.into()
+    }
+}
+
+impl Callable for Has {
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        arguments: Vec<Value>,
+    ) -> Result<Value, RuntimeError> {
+        if arguments.len() != 2 {
+            return Err(anyhow!("`has` expect a map and a key."))?;
+        }
+
+        let map = arguments[0].clone().map()?;
+        let has = map.borrow().contains_key(&arguments[1]);
+
+        Ok(has.into())
+    }
+
+    fn arity(&self) -> usize {
+        2
+    }
+
+    fn name(&self) -> &str {
+        "has"
+    }
+}
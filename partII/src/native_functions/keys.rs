@@ -0,0 +1,40 @@
+use std::rc::Rc;
+
+use anyhow::anyhow;
+
+use crate::{callable::Callable, error::RuntimeError, interpreter::Interpreter, value::Value};
+
+#[derive(Debug)]
+pub struct Keys {}
+
+impl Keys {
+    pub fn value() -> Value {
+        let keys = Rc::new(Self {}) as Rc<dyn Callable>;
+        keys.into()
+    }
+}
+
+impl Callable for Keys {
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        arguments: Vec<Value>,
+    ) -> Result<Value, RuntimeError> {
+        if arguments.len() != 1 {
+            return Err(anyhow!("`keys` expect one map argument."))?;
+        }
+
+        let map = arguments[0].clone().map()?;
+        let keys: Vec<_> = map.borrow().keys().cloned().collect();
+
+        Ok(keys.into())
+    }
+
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn name(&self) -> &str {
+        "keys"
+    }
+}
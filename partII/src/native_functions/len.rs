@@ -0,0 +1,44 @@
+use std::rc::Rc;
+
+use anyhow::anyhow;
+
+use crate::{callable::Callable, error::RuntimeError, interpreter::Interpreter, value::Value};
+
+#[derive(Debug)]
+pub struct Len {}
+
+impl Len {
+    pub fn value() -> Value {
+        let len = Rc::new(Self {}) as Rc<dyn Callable>;
+        len.into()
+    }
+}
+
+impl Callable for Len {
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        arguments: Vec<Value>,
+    ) -> Result<Value, RuntimeError> {
+        if arguments.len() != 1 {
+            return Err(anyhow!("`len` expect one list, map or string argument."))?;
+        }
+
+        let len = match &arguments[0] {
+            Value::List(list) => list.borrow().len(),
+            Value::Map(map) => map.borrow().len(),
+            Value::String(s) => s.chars().count(),
+            value => return Err(anyhow!("`len` can't be applied to {}.", value))?,
+        };
+
+        Ok((len as f64).into())
+    }
+
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn name(&self) -> &str {
+        "len"
+    }
+}
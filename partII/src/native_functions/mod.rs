@@ -1,7 +1,21 @@
 mod clock;
+mod has;
+mod keys;
+mod len;
 mod parse_int;
+mod pop;
+mod push;
 mod read_lines;
+mod remove;
+mod values;
 
 pub use clock::*;
+pub use has::*;
+pub use keys::*;
+pub use len::*;
 pub use parse_int::*;
+pub use pop::*;
+pub use push::*;
 pub use read_lines::*;
+pub use remove::*;
+pub use values::*;
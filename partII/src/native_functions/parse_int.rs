@@ -16,7 +16,7 @@ impl ParseInt {
 
 impl Callable for ParseInt {
     fn call(
-        &mut self,
+        &self,
         _interpreter: &mut Interpreter,
         arguments: Vec<Value>,
     ) -> Result<Value, RuntimeError> {
@@ -36,4 +36,8 @@ impl Callable for ParseInt {
     fn arity(&self) -> usize {
         1
     }
+
+    fn name(&self) -> &str {
+        "parseInt"
+    }
 }
@@ -0,0 +1,40 @@
+use std::rc::Rc;
+
+use anyhow::anyhow;
+
+use crate::{callable::Callable, error::RuntimeError, interpreter::Interpreter, value::Value};
+
+#[derive(Debug)]
+pub struct Pop {}
+
+impl Pop {
+    pub fn value() -> Value {
+        let pop = Rc::new(Self {}) as Rc<dyn Callable>;
+        pop.into()
+    }
+}
+
+impl Callable for Pop {
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        arguments: Vec<Value>,
+    ) -> Result<Value, RuntimeError> {
+        if arguments.len() != 1 {
+            return Err(anyhow!("`pop` expect one list argument."))?;
+        }
+
+        let list = arguments[0].clone().list()?;
+        let value = list.borrow_mut().pop();
+
+        Ok(value.ok_or_else(|| anyhow!("Can't pop from an empty list."))?)
+    }
+
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn name(&self) -> &str {
+        "pop"
+    }
+}
@@ -0,0 +1,41 @@
+use std::rc::Rc;
+
+use anyhow::anyhow;
+
+use crate::{callable::Callable, error::RuntimeError, interpreter::Interpreter, value::Value};
+
+#[derive(Debug)]
+pub struct Push {}
+
+impl Push {
+    pub fn value() -> Value {
+        let push = Rc::new(Self {}) as Rc<dyn Callable>;
+        push.into()
+    }
+}
+
+impl Callable for Push {
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        mut arguments: Vec<Value>,
+    ) -> Result<Value, RuntimeError> {
+        if arguments.len() != 2 {
+            return Err(anyhow!("`push` expect a list and a value."))?;
+        }
+
+        let value = arguments.pop().unwrap();
+        let list = arguments.pop().unwrap().list()?;
+        list.borrow_mut().push(value);
+
+        Ok(Value::Nil)
+    }
+
+    fn arity(&self) -> usize {
+        2
+    }
+
+    fn name(&self) -> &str {
+        "push"
+    }
+}
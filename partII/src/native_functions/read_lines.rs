@@ -16,7 +16,7 @@ impl ReadLines {
 
 impl Callable for ReadLines {
     fn call(
-        &mut self,
+        &self,
         _interpreter: &mut Interpreter,
         arguments: Vec<Value>,
     ) -> Result<Value, RuntimeError> {
@@ -40,4 +40,8 @@ impl Callable for ReadLines {
     fn arity(&self) -> usize {
         0
     }
+
+    fn name(&self) -> &str {
+        "readLines"
+    }
 }
@@ -0,0 +1,41 @@
+use std::rc::Rc;
+
+use anyhow::anyhow;
+
+use crate::{callable::Callable, error::RuntimeError, interpreter::Interpreter, value::Value};
+
+#[derive(Debug)]
+pub struct Remove {}
+
+impl Remove {
+    pub fn value() -> Value {
+        let remove = Rc::new(Self {}) as Rc<dyn Callable>;
+        remove.into()
+    }
+}
+
+impl Callable for Remove {
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        arguments: Vec<Value>,
+    ) -> Result<Value, RuntimeError> {
+        if arguments.len() != 2 {
+            return Err(anyhow!("`remove` expect a map and a key."))?;
+        }
+
+        // keep the order of the other entries
+        let map = arguments[0].clone().map()?;
+        let value = map.borrow_mut().shift_remove(&arguments[1]);
+
+        Ok(value.unwrap_or_default())
+    }
+
+    fn arity(&self) -> usize {
+        2
+    }
+
+    fn name(&self) -> &str {
+        "remove"
+    }
+}
@@ -0,0 +1,40 @@
+use std::rc::Rc;
+
+use anyhow::anyhow;
+
+use crate::{callable::Callable, error::RuntimeError, interpreter::Interpreter, value::Value};
+
+#[derive(Debug)]
+pub struct Values {}
+
+impl Values {
+    pub fn value() -> Value {
+        let values = Rc::new(Self {}) as Rc<dyn Callable>;
+        values.into()
+    }
+}
+
+impl Callable for Values {
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        arguments: Vec<Value>,
+    ) -> Result<Value, RuntimeError> {
+        if arguments.len() != 1 {
+            return Err(anyhow!("`values` expect one map argument."))?;
+        }
+
+        let map = arguments[0].clone().map()?;
+        let values: Vec<_> = map.borrow().values().cloned().collect();
+
+        Ok(values.into())
+    }
+
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn name(&self) -> &str {
+        "values"
+    }
+}
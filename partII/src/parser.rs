@@ -1,7 +1,8 @@
-use std::rc::Rc;
+use std::{collections::HashMap, rc::Rc};
 
 use crate::{
     callable,
+    cst::{Builder, NodeKind, Tree},
     error::{ParserError, ParserErrors},
     expr::Expr,
     stmt::Stmt,
@@ -15,14 +16,72 @@ type Result<T> = std::result::Result<T, ParserError>;
 pub struct Parser {
     tokens: Vec<Token>,
     current: usize,
+    /// Doc comments, by index of the token following them.
+    docs: HashMap<usize, String>,
+    /// Whitespace and comments, by index of the token following them.
+    trivia: HashMap<usize, Vec<Token>>,
+    /// The concrete syntax tree, when asked for one.
+    cst: Option<Builder>,
 }
 
 impl Parser {
     pub fn new(tokens: Vec<Token>) -> Self {
-        Self { tokens, current: 0 }
+        // doc comments and other trivia are not part of the grammar, set them
+        // aside with the token they precede
+        let mut docs = HashMap::new();
+        let mut doc: Option<String> = None;
+        let mut trivia: HashMap<usize, Vec<Token>> = HashMap::new();
+        let mut code = Vec::with_capacity(tokens.len());
+
+        for token in tokens {
+            if token.ty.is_trivia() {
+                if let TokenType::DocComment(line) = &token.ty {
+                    match &mut doc {
+                        Some(doc) => {
+                            doc.push('\n');
+                            doc.push_str(line);
+                        }
+                        None => doc = Some(line.clone()),
+                    }
+                }
+                trivia.entry(code.len()).or_default().push(token);
+            } else {
+                if let Some(doc) = doc.take() {
+                    docs.insert(code.len(), doc);
+                }
+                code.push(token);
+            }
+        }
+
+        Self {
+            tokens: code,
+            current: 0,
+            docs,
+            trivia,
+            cst: None,
+        }
     }
 
     pub fn parse(mut self) -> std::result::Result<Vec<Stmt>, ParserErrors> {
+        self.declarations()
+    }
+
+    /// Parse into a lossless concrete syntax tree, for tokens scanned
+    /// [`with_trivia`](crate::scanner::Scanner::with_trivia).
+    pub fn parse_cst(mut self) -> std::result::Result<Tree, ParserErrors> {
+        self.cst = Some(Builder::default());
+        self.declarations()?;
+
+        // the trailing trivia go with the end of file
+        let trivia = self.trivia.remove(&self.current).unwrap_or_default();
+        let eof = self.peek().clone();
+        let mut cst = self.cst.unwrap_or_default();
+        cst.token(trivia, eof);
+
+        Ok(cst.finish())
+    }
+
+    fn declarations(&mut self) -> std::result::Result<Vec<Stmt>, ParserErrors> {
         let mut stmts = Vec::new();
         let mut errors = Vec::new();
 
@@ -43,7 +102,8 @@ impl Parser {
     fn declaration(&mut self) -> Result<Stmt> {
         let result = if self.follow([TokenType::Class]) {
             self.class_declaration()
-        } else if self.follow([TokenType::Fun]) {
+        } else if self.check(&TokenType::Fun) && !self.check_next(&TokenType::LeftParen) {
+            self.advance();
             self.function_declaration()
         } else if self.follow([TokenType::Var]) {
             self.var_declaration()
@@ -59,9 +119,11 @@ impl Parser {
     }
 
     fn class_declaration(&mut self) -> Result<Stmt> {
-        let name = self.consume_ident(format!("Expect class name."))?;
+        let start = self.current - 1;
+        let doc = self.docs.remove(&start);
+        let name = self.consume_ident("Expect class name.")?;
         let superclass = if self.follow([TokenType::Less]) {
-            let name = self.consume_ident(format!("Expect superclass name"))?;
+            let name = self.consume_ident("Expect superclass name.")?;
             Some(Expr::Variable { name })
         } else {
             None
@@ -70,37 +132,57 @@ impl Parser {
 
         let mut methods = Vec::new();
         while !self.check(&TokenType::RightBrace) && !self.is_at_end() {
-            methods.push(self.function("method")?);
+            let method = self.current;
+            let doc = self.docs.remove(&method);
+            methods.push(callable::Function {
+                doc,
+                ..self.function("method")?
+            });
+            self.node(NodeKind::Function, method);
         }
 
         self.consume(&TokenType::RightBrace, "Expect `}` after class body.")?;
+        self.node(NodeKind::Class, start);
 
         Ok(Stmt::Class {
             name,
             superclass,
             methods,
+            doc,
         })
     }
 
     fn function_declaration(&mut self) -> Result<Stmt> {
-        Ok(Stmt::Function(self.function("function")?))
+        let start = self.current - 1;
+        let doc = self.docs.remove(&start);
+        let function = callable::Function {
+            doc,
+            ..self.function("function")?
+        };
+        self.node(NodeKind::Function, start);
+
+        Ok(Stmt::Function(function))
     }
 
     fn function(&mut self, kind: &str) -> Result<callable::Function> {
         let name = self.consume_ident(format!("Expect {kind} name."))?;
-
         self.consume(
             &TokenType::LeftParen,
-            &format!("Expect `(` after {kind} name."),
+            format!("Expect `(` after {kind} name."),
         )?;
+        self.function_rest(name, kind)
+    }
 
+    /// Parse the parameters and the body of a function named `name`, after
+    /// its opening `(`.
+    fn function_rest(&mut self, name: Token, kind: &str) -> Result<callable::Function> {
         let mut params = Vec::new();
 
         if !self.check(&TokenType::RightParen) {
             params.push(self.consume_ident("Expect parameter name.")?);
             while self.follow([TokenType::Comma]) {
                 if params.len() >= 255 {
-                    return Err(ParserError::TooManyParameters);
+                    return Err(ParserError::TooManyParameters(self.peek().clone()));
                 }
                 params.push(self.consume_ident("Expect parameter name.")?);
             }
@@ -123,21 +205,24 @@ impl Parser {
             body: Rc::new(body),
             is_initializer,
             closure: None,
+            doc: None,
         })
     }
 
     fn var_declaration(&mut self) -> Result<Stmt> {
+        let start = self.current - 1;
         let name = self.consume_ident("Expect variable name.")?;
         let mut initializer = None;
 
         if self.follow([TokenType::Equal]) {
-            initializer = Some(self.expression()?);
+            initializer = Some(self.assignment()?);
         }
 
         self.consume(
             &TokenType::Semicolon,
             "Expect `;` after variable declaration.",
         )?;
+        self.node(NodeKind::Var, start);
 
         Ok(Stmt::Var { name, initializer })
     }
@@ -153,6 +238,16 @@ impl Parser {
             self.while_statement()
         } else if self.follow([TokenType::For]) {
             self.for_statement()
+        } else if self.follow([TokenType::Break]) {
+            let keyword = self.previous().clone();
+            self.consume(&TokenType::Semicolon, "Expect `;` after `break`.")?;
+            self.node(NodeKind::Break, self.current - 2);
+            Ok(Stmt::Break(keyword))
+        } else if self.follow([TokenType::Continue]) {
+            let keyword = self.previous().clone();
+            self.consume(&TokenType::Semicolon, "Expect `;` after `continue`.")?;
+            self.node(NodeKind::Continue, self.current - 2);
+            Ok(Stmt::Continue(keyword))
         } else if self.follow([TokenType::LeftBrace]) {
             Ok(Stmt::Block(Rc::new(self.block()?)))
         } else {
@@ -161,6 +256,7 @@ impl Parser {
     }
 
     fn if_statement(&mut self) -> Result<Stmt> {
+        let start = self.current - 1;
         self.consume(&TokenType::LeftParen, "Expect `(` after `if`.")?;
         let condition = self.expression()?;
         self.consume(&TokenType::RightParen, "Expect `)` after `if` condition.")?;
@@ -171,6 +267,7 @@ impl Parser {
         if self.follow([TokenType::Else]) {
             else_branch = Some(self.statement()?);
         }
+        self.node(NodeKind::If, start);
 
         Ok(Stmt::If {
             condition,
@@ -180,13 +277,17 @@ impl Parser {
     }
 
     fn print_statement(&mut self) -> Result<Stmt> {
+        let start = self.current - 1;
+        let keyword = self.previous().clone();
         let value = self.expression()?;
         self.consume(&TokenType::Semicolon, "Expect `;` after value.")?;
+        self.node(NodeKind::Print, start);
 
-        Ok(Stmt::Print(value))
+        Ok(Stmt::Print { keyword, value })
     }
 
     fn return_statement(&mut self) -> Result<Stmt> {
+        let start = self.current - 1;
         let keyword = self.previous().clone();
 
         let value = if self.check(&TokenType::Semicolon) {
@@ -196,11 +297,13 @@ impl Parser {
         };
 
         self.consume(&TokenType::Semicolon, "Expect `;` after return.")?;
+        self.node(NodeKind::Return, start);
 
         Ok(Stmt::Return { keyword, value })
     }
 
     fn while_statement(&mut self) -> Result<Stmt> {
+        let start = self.current - 1;
         self.consume(&TokenType::LeftParen, "Expect `(` after `while`.")?;
         let condition = self.expression()?;
         self.consume(
@@ -209,14 +312,17 @@ impl Parser {
         )?;
 
         let body = self.statement()?;
+        self.node(NodeKind::While, start);
 
         Ok(Stmt::While {
             condition,
             body: Box::new(body),
+            increment: None,
         })
     }
 
     fn for_statement(&mut self) -> Result<Stmt> {
+        let start = self.current - 1;
         self.consume(&TokenType::LeftParen, "Expect `(` after `for`.")?;
 
         let initializer = if self.follow([TokenType::Semicolon]) {
@@ -242,18 +348,17 @@ impl Parser {
 
         self.consume(&TokenType::RightParen, "Expect `)` after for clauses.")?;
 
-        let mut body = self.statement()?;
-
-        // desugar the for loop
-        if let Some(increment) = increment {
-            body = Stmt::Block(Rc::new(vec![body, Stmt::Expression(increment)]));
-        }
+        let body = self.statement()?;
+        self.node(NodeKind::For, start);
 
+        // desugar the for loop, the increment stays apart from the body so a
+        // `continue` doesn't skip it
         let condition = condition.unwrap_or(Expr::literal(true));
 
-        body = Stmt::While {
+        let mut body = Stmt::While {
             condition,
             body: Box::new(body),
+            increment,
         };
 
         if let Some(initializer) = initializer {
@@ -263,7 +368,9 @@ impl Parser {
         Ok(body)
     }
 
+    /// Parse the statements of a block, after its opening `{`.
     fn block(&mut self) -> Result<Vec<Stmt>> {
+        let start = self.current - 1;
         let mut statements = Vec::new();
 
         while !self.check(&TokenType::RightBrace) && !self.is_at_end() {
@@ -271,27 +378,50 @@ impl Parser {
         }
 
         self.consume(&TokenType::RightBrace, "Expect `}` after block.")?;
+        self.node(NodeKind::Block, start);
 
         Ok(statements)
     }
 
     fn expression_statement(&mut self) -> Result<Stmt> {
+        let start = self.current;
         let expr = self.expression()?;
         self.consume(&TokenType::Semicolon, "Expect `;` after expression.")?;
+        self.node(NodeKind::Expression, start);
 
         Ok(Stmt::Expression(expr))
     }
 
     fn expression(&mut self) -> Result<Expr> {
-        self.assignment()
+        self.comma()
+    }
+
+    /// `a, b` evaluates `a`, then `b`, and yields `b`.
+    ///
+    /// Arguments, list elements and map entries are already separated by
+    /// commas, so they are parsed as assignments.
+    fn comma(&mut self) -> Result<Expr> {
+        let start = self.current;
+        let mut expr = self.assignment()?;
+
+        while self.follow([TokenType::Comma]) {
+            let operator = self.previous().clone();
+            let right = self.assignment()?;
+            self.node(NodeKind::Binary, start);
+            expr = Expr::binary(expr, operator, right);
+        }
+
+        Ok(expr)
     }
 
     fn assignment(&mut self) -> Result<Expr> {
-        let expr = self.or()?;
+        let start = self.current;
+        let expr = self.conditional()?;
 
         if self.follow([TokenType::Equal]) {
             let equals = self.previous().clone();
             let value = self.assignment()?;
+            self.node(NodeKind::Assign, start);
 
             if let Expr::Variable { name } = expr {
                 return Ok(Expr::Assign {
@@ -304,20 +434,70 @@ impl Parser {
                     name,
                     value: Box::new(value),
                 });
+            } else if let Expr::Index {
+                object,
+                bracket,
+                index,
+            } = expr
+            {
+                return Ok(Expr::SetIndex {
+                    object,
+                    bracket,
+                    index,
+                    value: Box::new(value),
+                });
             }
 
             return Err(ParserError::InvalidAssignmentTarget(equals));
+        } else if self.follow([
+            TokenType::PlusEqual,
+            TokenType::MinusEqual,
+            TokenType::StarEqual,
+            TokenType::SlashEqual,
+        ]) {
+            let operator = self.previous().clone();
+            let value = self.assignment()?;
+            self.node(NodeKind::Assign, start);
+            return Expr::update(expr, operator, value, false);
         }
 
         Ok(expr)
     }
 
+    fn conditional(&mut self) -> Result<Expr> {
+        let start = self.current;
+        let condition = self.or()?;
+
+        if self.follow([TokenType::Question]) {
+            let question = self.previous().clone();
+            let then_branch = self.expression()?;
+            self.consume(
+                &TokenType::Colon,
+                "Expect `:` after the then branch of a conditional.",
+            )?;
+            // right-associative: `a ? b : c ? d : e` is `a ? b : (c ? d : e)`
+            let else_branch = self.conditional()?;
+            self.node(NodeKind::Conditional, start);
+
+            return Ok(Expr::Conditional {
+                condition: Box::new(condition),
+                question,
+                then_branch: Box::new(then_branch),
+                else_branch: Box::new(else_branch),
+            });
+        }
+
+        Ok(condition)
+    }
+
     fn or(&mut self) -> Result<Expr> {
+        let start = self.current;
         let mut expr = self.and()?;
 
         while self.follow([TokenType::Or]) {
             let operator = self.previous().clone();
             let right = self.and()?;
+            self.node(NodeKind::Logical, start);
             expr = Expr::logical(expr, operator, right);
         }
 
@@ -325,11 +505,13 @@ impl Parser {
     }
 
     fn and(&mut self) -> Result<Expr> {
-        let mut expr = self.comparison()?;
+        let start = self.current;
+        let mut expr = self.equality()?;
 
         while self.follow([TokenType::And]) {
             let operator = self.previous().clone();
-            let right = self.comparison()?;
+            let right = self.equality()?;
+            self.node(NodeKind::Logical, start);
             expr = Expr::logical(expr, operator, right);
         }
 
@@ -337,11 +519,13 @@ impl Parser {
     }
 
     fn equality(&mut self) -> Result<Expr> {
+        let start = self.current;
         let mut expr = self.comparison()?;
 
         while self.follow([TokenType::BangEqual, TokenType::EqualEqual]) {
             let operator = self.previous().clone();
             let right = self.comparison()?;
+            self.node(NodeKind::Binary, start);
             expr = Expr::binary(expr, operator, right);
         }
 
@@ -349,6 +533,7 @@ impl Parser {
     }
 
     fn comparison(&mut self) -> Result<Expr> {
+        let start = self.current;
         let mut expr = self.term()?;
 
         while self.follow([
@@ -359,6 +544,7 @@ impl Parser {
         ]) {
             let operator = self.previous().clone();
             let right = self.term()?;
+            self.node(NodeKind::Binary, start);
             expr = Expr::binary(expr, operator, right);
         }
 
@@ -366,11 +552,13 @@ impl Parser {
     }
 
     fn term(&mut self) -> Result<Expr> {
+        let start = self.current;
         let mut expr = self.factor()?;
 
         while self.follow([TokenType::Minus, TokenType::Plus]) {
             let operator = self.previous().clone();
             let right = self.factor()?;
+            self.node(NodeKind::Binary, start);
             expr = Expr::binary(expr, operator, right);
         }
 
@@ -378,11 +566,18 @@ impl Parser {
     }
 
     fn factor(&mut self) -> Result<Expr> {
+        let start = self.current;
         let mut expr = self.unary()?;
 
-        while self.follow([TokenType::Slash, TokenType::Star]) {
+        while self.follow([
+            TokenType::Slash,
+            TokenType::Star,
+            TokenType::Percent,
+            TokenType::TildeSlash,
+        ]) {
             let operator = self.previous().clone();
             let right = self.unary()?;
+            self.node(NodeKind::Binary, start);
             expr = Expr::binary(expr, operator, right);
         }
 
@@ -390,29 +585,77 @@ impl Parser {
     }
 
     fn unary(&mut self) -> Result<Expr> {
+        let start = self.current;
         if self.follow([TokenType::Bang, TokenType::Minus]) {
             let operator = self.previous().clone();
             let right = self.unary()?;
+            self.node(NodeKind::Unary, start);
 
             Ok(Expr::unary(operator, right))
+        } else if self.follow([TokenType::PlusPlus, TokenType::MinusMinus]) {
+            let operator = self.previous().clone();
+            let target = self.unary()?;
+            self.node(NodeKind::Unary, start);
+
+            Expr::update(target, operator, Expr::literal(1.0), false)
         } else {
-            self.call()
+            self.exponent()
+        }
+    }
+
+    /// `**` binds tighter than the prefix operators, so `-2 ** 2` is `-(2 ** 2)`,
+    /// and is right-associative.
+    fn exponent(&mut self) -> Result<Expr> {
+        let start = self.current;
+        let expr = self.postfix()?;
+
+        if self.follow([TokenType::StarStar]) {
+            let operator = self.previous().clone();
+            let right = self.unary()?;
+            self.node(NodeKind::Binary, start);
+            return Ok(Expr::binary(expr, operator, right));
         }
+
+        Ok(expr)
+    }
+
+    fn postfix(&mut self) -> Result<Expr> {
+        let start = self.current;
+        let expr = self.call()?;
+
+        if self.follow([TokenType::PlusPlus, TokenType::MinusMinus]) {
+            let operator = self.previous().clone();
+            self.node(NodeKind::Postfix, start);
+            return Expr::update(expr, operator, Expr::literal(1.0), true);
+        }
+
+        Ok(expr)
     }
 
     fn call(&mut self) -> Result<Expr> {
+        let start = self.current;
         let mut expr = self.primary()?;
 
         loop {
             if self.follow([TokenType::LeftParen]) {
                 expr = self.finish_call(expr)?;
-            }
-            if self.follow([TokenType::Dot]) {
+                self.node(NodeKind::Call, start);
+            } else if self.follow([TokenType::Dot]) {
                 let name = self.consume_ident("Expect property name aften `.`.")?;
+                self.node(NodeKind::Get, start);
                 expr = Expr::Get {
                     name,
                     object: Box::new(expr),
                 };
+            } else if self.follow([TokenType::LeftBracket]) {
+                let index = self.expression()?;
+                let bracket = self.consume(&TokenType::RightBracket, "Expect `]` after index.")?;
+                self.node(NodeKind::Index, start);
+                expr = Expr::Index {
+                    object: Box::new(expr),
+                    bracket,
+                    index: Box::new(index),
+                };
             } else {
                 break Ok(expr);
             }
@@ -423,12 +666,12 @@ impl Parser {
         let mut arguments = Vec::new();
 
         if !self.check(&TokenType::RightParen) {
-            arguments.push(self.expression()?);
+            arguments.push(self.assignment()?);
             while self.follow([TokenType::Comma]) {
                 if arguments.len() >= 255 {
-                    return Err(ParserError::TooManyArguments);
+                    return Err(ParserError::TooManyArguments(self.peek().clone()));
                 }
-                arguments.push(self.expression()?);
+                arguments.push(self.assignment()?);
             }
         }
 
@@ -442,32 +685,110 @@ impl Parser {
     }
 
     fn primary(&mut self) -> Result<Expr> {
+        let start = self.current;
         let token = self.advance();
-        let expr = match token.ty {
-            TokenType::Nil => Expr::literal(Value::Nil),
-            TokenType::False => Expr::literal(false),
-            TokenType::True => Expr::literal(true),
-            TokenType::Number(n) => Expr::literal(n),
-            TokenType::String(ref s) => Expr::literal(s.to_string()),
+        let (kind, expr) = match token.ty {
+            TokenType::Nil => (NodeKind::Literal, Expr::literal(Value::Nil)),
+            TokenType::False => (NodeKind::Literal, Expr::literal(false)),
+            TokenType::True => (NodeKind::Literal, Expr::literal(true)),
+            TokenType::Number(n) => (NodeKind::Literal, Expr::literal(n)),
+            TokenType::String(ref s) => (NodeKind::Literal, Expr::literal(s.to_string())),
+            TokenType::Interpolation(ref part) => {
+                // desugar `"a${b}c"` into `"a" + b + "c"`, the string on the
+                // left makes every `+` a concatenation
+                let plus = Token {
+                    ty: TokenType::Plus,
+                    lexeme: String::from("+"),
+                    span: token.span,
+                };
+                let mut expr = Expr::literal(part.to_string());
+                let expr = loop {
+                    let value = self.expression()?;
+                    expr = Expr::binary(expr, plus.clone(), value);
+
+                    let token = self.advance().clone();
+                    match token.ty {
+                        TokenType::Interpolation(part) => {
+                            expr = Expr::binary(expr, plus.clone(), Expr::literal(part));
+                        }
+                        TokenType::String(rest) => {
+                            break Expr::binary(expr, plus, Expr::literal(rest));
+                        }
+                        _ => {
+                            return Err(ParserError::Consume(
+                                token,
+                                String::from("Expect `}` after interpolated expression."),
+                            ))
+                        }
+                    }
+                };
+                (NodeKind::Interpolation, expr)
+            }
             TokenType::LeftParen => {
                 let expr = self.expression()?;
                 self.consume(&TokenType::RightParen, "Expect `)` after expression.")?;
-                Expr::group(expr)
+                (NodeKind::Grouping, Expr::group(expr))
             }
-            TokenType::This => Expr::This {
-                keyword: token.clone(),
-            },
+            TokenType::LeftBracket => {
+                let mut elements = Vec::new();
+                while !self.check(&TokenType::RightBracket) {
+                    elements.push(self.assignment()?);
+                    if !self.follow([TokenType::Comma]) {
+                        break;
+                    }
+                }
+                let bracket =
+                    self.consume(&TokenType::RightBracket, "Expect `]` after list elements.")?;
+                (NodeKind::List, Expr::List { bracket, elements })
+            }
+            TokenType::LeftBrace => {
+                let mut entries = Vec::new();
+                while !self.check(&TokenType::RightBrace) {
+                    let key = self.assignment()?;
+                    self.consume(&TokenType::Colon, "Expect `:` after map key.")?;
+                    let value = self.assignment()?;
+                    entries.push((key, value));
+                    if !self.follow([TokenType::Comma]) {
+                        break;
+                    }
+                }
+                let brace =
+                    self.consume(&TokenType::RightBrace, "Expect `}` after map entries.")?;
+                (NodeKind::Map, Expr::Map { brace, entries })
+            }
+            TokenType::Fun => {
+                // lambdas are reported under the name `lambda` at their `fun`
+                let name = Token {
+                    lexeme: String::from("lambda"),
+                    ..token.clone()
+                };
+                self.consume(&TokenType::LeftParen, "Expect `(` after `fun`.")?;
+                (
+                    NodeKind::Lambda,
+                    Expr::Lambda(self.function_rest(name, "lambda")?),
+                )
+            }
+            TokenType::This => (
+                NodeKind::This,
+                Expr::This {
+                    keyword: token.clone(),
+                },
+            ),
             TokenType::Super => {
                 let keyword = token.clone();
                 self.consume(&TokenType::Dot, "Expect `.` after `super`.")?;
                 let method = self.consume_ident("Expect superclass method name.")?;
-                Expr::Super { keyword, method }
+                (NodeKind::Super, Expr::Super { keyword, method })
             }
-            TokenType::Identifier(_) => Expr::Variable {
-                name: token.clone(),
-            },
-            _ => return Err(ParserError::ExpectingExpression),
+            TokenType::Identifier(_) => (
+                NodeKind::Variable,
+                Expr::Variable {
+                    name: token.clone(),
+                },
+            ),
+            _ => return Err(ParserError::ExpectingExpression(token.clone())),
         };
+        self.node(kind, start);
 
         Ok(expr)
     }
@@ -476,11 +797,10 @@ impl Parser {
         if self.check(ty) {
             Ok(self.advance().clone())
         } else {
-            Err(ParserError::Consume(format!(
-                "Got `{}`. {}",
-                self.peek().lexeme,
-                msg.as_ref()
-            )))
+            Err(ParserError::Consume(
+                self.peek().clone(),
+                msg.as_ref().to_string(),
+            ))
         }
     }
 
@@ -489,11 +809,10 @@ impl Parser {
         if matches!(self.peek().ty, TokenType::Identifier(_)) {
             Ok(self.advance().clone())
         } else {
-            Err(ParserError::Consume(format!(
-                "Got `{}`. {}",
-                self.peek().lexeme,
-                msg.as_ref()
-            )))
+            Err(ParserError::Consume(
+                self.peek().clone(),
+                msg.as_ref().to_string(),
+            ))
         }
     }
 
@@ -511,13 +830,31 @@ impl Parser {
         (!self.is_at_end()) && (&self.peek().ty == ty)
     }
 
+    fn check_next(&mut self, ty: &TokenType) -> bool {
+        self.tokens
+            .get(self.current + 1)
+            .is_some_and(|token| &token.ty == ty)
+    }
+
     fn advance(&mut self) -> &Token {
         if !self.is_at_end() {
+            if let Some(cst) = &mut self.cst {
+                let trivia = self.trivia.remove(&self.current).unwrap_or_default();
+                cst.token(trivia, self.tokens[self.current].clone());
+            }
             self.current += 1;
         }
         self.previous()
     }
 
+    /// Close a node of the concrete syntax tree, from the `start`th token to
+    /// the last one consumed.
+    fn node(&mut self, kind: NodeKind, start: usize) {
+        if let Some(cst) = &mut self.cst {
+            cst.node(kind, start);
+        }
+    }
+
     fn is_at_end(&self) -> bool {
         self.peek().ty == TokenType::EoF
     }
@@ -539,7 +876,9 @@ impl Parser {
             }
 
             match self.peek().ty {
-                TokenType::Class
+                TokenType::Break
+                | TokenType::Class
+                | TokenType::Continue
                 | TokenType::For
                 | TokenType::Fun
                 | TokenType::If
@@ -1,17 +1,44 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
-use crate::{callable::Function, expr::Expr, interpreter::Interpreter, stmt::Stmt, token::Token};
+use crate::{
+    callable::Function,
+    error::{ResolverError, ResolverErrors, ResolverWarning, ResolverWarnings},
+    expr::Expr,
+    interpreter::Interpreter,
+    stmt::Stmt,
+    token::Token,
+};
 
-use anyhow::{anyhow, Result};
-
-type Scope<'a> = HashMap<&'a str, bool>;
+type Scope<'a> = HashMap<&'a str, Variable<'a>>;
 
 #[derive(Debug)]
 pub struct Resolver<'a> {
     interpreter: &'a mut Interpreter,
     scopes: Vec<Scope<'a>>,
+    /// Globals declared by the script so far, the ones defined before it ran
+    /// are in the interpreter.
+    globals: HashSet<&'a str>,
     current_function: FunctionType,
     current_class: ClassType,
+    /// Number of loops around the current statement, in the current function.
+    loop_depth: usize,
+    errors: Vec<ResolverError>,
+    warnings: Vec<ResolverWarning>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Variable<'a> {
+    kind: VariableKind<'a>,
+    defined: bool,
+    read: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum VariableKind<'a> {
+    Local(&'a Token),
+    Parameter(&'a Token),
+    /// `this` and `super`, which are never declared by the user.
+    Implicit,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -26,6 +53,7 @@ enum FunctionType {
 enum ClassType {
     None,
     Class,
+    Subclass,
 }
 
 impl<'a> Resolver<'a> {
@@ -33,13 +61,31 @@ impl<'a> Resolver<'a> {
         Self {
             interpreter,
             scopes: Vec::new(),
+            globals: HashSet::new(),
             current_function: FunctionType::None,
             current_class: ClassType::None,
+            loop_depth: 0,
+            errors: Vec::new(),
+            warnings: Vec::new(),
+        }
+    }
+
+    /// Resolve every statement, reporting all the static errors at once.
+    pub fn resolve(&mut self, stmts: &'a [Stmt]) -> Result<(), ResolverErrors> {
+        self.resolve_stmts(stmts);
+
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ResolverErrors(std::mem::take(&mut self.errors)))
         }
     }
 
-    pub fn resolve(&mut self, stmts: &'a [Stmt]) -> Result<()> {
-        self.resolve_stmts(stmts)
+    /// The warnings found while resolving, they don't prevent the script from running.
+    pub fn warnings(mut self) -> ResolverWarnings {
+        self.warnings
+            .sort_by_key(|warning| warning.token().span.offset);
+        ResolverWarnings(self.warnings)
     }
 
     fn begin_scope(&mut self) {
@@ -47,59 +93,156 @@ impl<'a> Resolver<'a> {
     }
 
     fn end_scope(&mut self) {
-        self.scopes.pop();
+        let scope = self.scopes.pop().unwrap_or_default();
+
+        let unused = scope
+            .into_values()
+            .filter(|variable| !variable.read)
+            .filter_map(|variable| match variable.kind {
+                VariableKind::Local(name) if !name.lexeme.starts_with('_') => {
+                    Some(ResolverWarning::UnusedLocal(name.clone()))
+                }
+                VariableKind::Parameter(name) if !name.lexeme.starts_with('_') => {
+                    Some(ResolverWarning::UnusedParameter(name.clone()))
+                }
+                _ => None,
+            });
+
+        self.warnings.extend(unused);
+    }
+
+    fn error(&mut self, error: ResolverError) {
+        self.errors.push(error);
     }
 
-    fn declare(&mut self, name: &'a Token) -> Result<()> {
+    fn declare(&mut self, name: &'a Token) {
+        self.declare_variable(VariableKind::Local(name), name)
+    }
+
+    fn declare_parameter(&mut self, name: &'a Token) {
+        self.declare_variable(VariableKind::Parameter(name), name)
+    }
+
+    fn declare_implicit(&mut self, name: &'a str) {
         if let Some(scope) = self.scopes.last_mut() {
-            if scope.contains_key(name.lexeme.as_str()) {
-                return Err(anyhow!("Already a variable with this name in this scope."))?;
-            }
-            scope.insert(&name.lexeme, false);
+            let variable = Variable {
+                kind: VariableKind::Implicit,
+                defined: true,
+                read: false,
+            };
+            scope.insert(name, variable);
         }
-        Ok(())
+    }
+
+    fn declare_variable(&mut self, kind: VariableKind<'a>, name: &'a Token) {
+        let shadows = self
+            .scopes
+            .iter()
+            .rev()
+            .skip(1)
+            .any(|scope| scope.contains_key(name.lexeme.as_str()))
+            || self.is_global(&name.lexeme);
+
+        let Some(scope) = self.scopes.last_mut() else {
+            self.globals.insert(&name.lexeme);
+            return;
+        };
+        if scope.contains_key(name.lexeme.as_str()) {
+            self.errors
+                .push(ResolverError::AlreadyDeclared(name.clone()));
+            return;
+        }
+        let variable = Variable {
+            kind,
+            defined: false,
+            read: false,
+        };
+        scope.insert(&name.lexeme, variable);
+
+        if shadows {
+            self.warnings.push(ResolverWarning::Shadowing(name.clone()));
+        }
+    }
+
+    /// Whether `name` is a global of the script, a native function, or a
+    /// global of an earlier line in the REPL.
+    fn is_global(&self, name: &str) -> bool {
+        self.globals.contains(name) || self.interpreter.globals.borrow().contains(name)
     }
 
     fn define(&mut self, name: &'a Token) {
-        if let Some(scope) = self.scopes.last_mut() {
-            scope.insert(&name.lexeme, true);
+        if let Some(variable) = self
+            .scopes
+            .last_mut()
+            .and_then(|scope| scope.get_mut(name.lexeme.as_str()))
+        {
+            variable.defined = true;
         }
     }
 
-    fn resolve_stmts(&mut self, statements: &'a [Stmt]) -> Result<()> {
+    fn resolve_stmts(&mut self, statements: &'a [Stmt]) {
+        let mut returned: Option<&Token> = None;
+
         for statement in statements {
-            statement.resolve(self)?;
+            if let Some(keyword) = returned.take() {
+                let code = statement.token().unwrap_or(keyword);
+                self.warnings.push(ResolverWarning::UnreachableCode {
+                    keyword: keyword.clone(),
+                    code: code.clone(),
+                });
+            }
+            if let Stmt::Return { keyword, .. } | Stmt::Break(keyword) | Stmt::Continue(keyword) =
+                statement
+            {
+                returned = Some(keyword);
+            }
+            statement.resolve(self);
         }
-        Ok(())
     }
 
-    fn resolve_local(&mut self, expr: &'a Expr, name: &'a Token) -> Result<()> {
-        for (idx, scope) in self.scopes.iter().enumerate() {
-            if scope.contains_key(&name.lexeme as &str) {
-                self.interpreter.resolve(expr, self.scopes.len() - idx - 1);
-                return Ok(());
+    /// Resolve a variable being read.
+    fn resolve_local(&mut self, expr: &'a Expr, name: &'a Token) {
+        self.resolve_depth(expr, name, true)
+    }
+
+    /// Resolve a variable being assigned, which doesn't count as using it.
+    fn resolve_assignment(&mut self, expr: &'a Expr, name: &'a Token) {
+        self.resolve_depth(expr, name, false)
+    }
+
+    fn resolve_depth(&mut self, expr: &'a Expr, name: &'a Token, read: bool) {
+        // the innermost scope wins when a variable is shadowed
+        for (depth, scope) in self.scopes.iter_mut().rev().enumerate() {
+            if let Some(variable) = scope.get_mut(name.lexeme.as_str()) {
+                variable.read |= read;
+                self.interpreter.resolve(expr, depth);
+                return;
             }
         }
-        Ok(())
     }
 
-    fn resolve_function(&mut self, function: &'a Function, ty: FunctionType) -> Result<()> {
+    fn resolve_function(&mut self, function: &'a Function, ty: FunctionType) {
+        let enclosing_function = self.current_function;
         self.current_function = ty;
+        // a loop around the declaration can't be broken from the body
+        let enclosing_loop_depth = std::mem::take(&mut self.loop_depth);
 
         self.begin_scope();
         for param in &function.params {
-            self.declare(param)?;
+            self.declare_parameter(param);
             self.define(param);
         }
 
-        self.resolve_stmts(&function.body)?;
+        self.resolve_stmts(&function.body);
         self.end_scope();
-        Ok(())
+
+        self.current_function = enclosing_function;
+        self.loop_depth = enclosing_loop_depth;
     }
 
     fn get(&self, name: &str) -> Option<bool> {
         if let Some(scope) = self.scopes.last() {
-            scope.get(name).copied()
+            scope.get(name).map(|variable| variable.defined)
         } else {
             None
         }
@@ -111,30 +254,34 @@ impl<'a> Resolver<'a> {
 }
 
 impl<'a> Stmt {
-    fn resolve(&'a self, resolver: &mut Resolver<'a>) -> Result<()> {
+    fn resolve(&'a self, resolver: &mut Resolver<'a>) {
         match self {
             Stmt::Class {
                 name,
                 superclass,
                 methods,
+                ..
             } => {
                 let enclosing_class = resolver.current_class;
                 resolver.current_class = ClassType::Class;
 
-                resolver.declare(name)?;
+                resolver.declare(name);
                 resolver.define(name);
                 if let Some(superclass) = superclass {
-                    if name.lexeme == superclass.unwrap_variable().lexeme {
-                        return Err(anyhow!("A class can't inherit from itself."));
+                    resolver.current_class = ClassType::Subclass;
+
+                    let superclass_name = superclass.unwrap_variable();
+                    if name.lexeme == superclass_name.lexeme {
+                        resolver.error(ResolverError::InheritFromItself(superclass_name.clone()));
                     }
-                    superclass.resolve(resolver)?;
+                    superclass.resolve(resolver);
 
                     resolver.begin_scope();
-                    resolver.scopes.last_mut().unwrap().insert("super", true);
+                    resolver.declare_implicit("super");
                 }
 
                 resolver.begin_scope();
-                resolver.scopes.last_mut().unwrap().insert("this", true);
+                resolver.declare_implicit("this");
 
                 for method in methods {
                     let declaration = if method.is_initializer {
@@ -142,27 +289,25 @@ impl<'a> Stmt {
                     } else {
                         FunctionType::Method
                     };
-                    resolver.resolve_function(method, declaration)?;
+                    resolver.resolve_function(method, declaration);
                 }
 
                 resolver.end_scope();
 
-                if let Some(_) = superclass {
+                if superclass.is_some() {
                     resolver.end_scope();
                 }
 
                 resolver.current_class = enclosing_class;
-                Ok(())
             }
             Stmt::Block(stmts) => {
                 resolver.begin_scope();
-                resolver.resolve_stmts(&stmts)?;
+                resolver.resolve_stmts(stmts);
                 resolver.end_scope();
-                Ok(())
             }
             Stmt::Expression(expr) => expr.resolve(resolver),
             Stmt::Function(function @ Function { name, .. }) => {
-                resolver.declare(name)?;
+                resolver.declare(name);
                 resolver.define(name);
                 resolver.resolve_function(function, FunctionType::Function)
             }
@@ -171,86 +316,143 @@ impl<'a> Stmt {
                 then_branch,
                 else_branch,
             } => {
-                condition.resolve(resolver)?;
-                then_branch.resolve(resolver)?;
+                condition.resolve(resolver);
+                then_branch.resolve(resolver);
                 if let Some(else_branch) = else_branch {
-                    else_branch.resolve(resolver)?
+                    else_branch.resolve(resolver);
                 }
-                Ok(())
             }
-            Stmt::Print(expr) => expr.resolve(resolver),
-            Stmt::Return {
-                value: Some(value), ..
-            } => {
+            Stmt::Print { value, .. } => value.resolve(resolver),
+            Stmt::Return { keyword, value } => {
                 if resolver.current_function == FunctionType::None {
-                    return Err(anyhow!("Can't return from top-level code."))?;
-                } else if resolver.current_function == FunctionType::Initializer {
-                    return Err(anyhow!("Can't return a value from an initializer."))?;
+                    resolver.error(ResolverError::TopLevelReturn(keyword.clone()));
+                }
+                if let Some(value) = value {
+                    if resolver.current_function == FunctionType::Initializer {
+                        resolver.error(ResolverError::ReturnFromInitializer(keyword.clone()));
+                    }
+                    value.resolve(resolver)
                 }
-                value.resolve(resolver)
             }
             Stmt::Var { name, initializer } => {
-                resolver.declare(name)?;
+                resolver.declare(name);
                 if let Some(initializer) = initializer {
-                    initializer.resolve(resolver)?;
+                    initializer.resolve(resolver);
                 }
                 resolver.define(name);
-                Ok(())
             }
-            Stmt::While { condition, body } => {
-                condition.resolve(resolver)?;
-                body.resolve(resolver)
+            Stmt::While {
+                condition,
+                body,
+                increment,
+            } => {
+                condition.resolve(resolver);
+                resolver.loop_depth += 1;
+                body.resolve(resolver);
+                resolver.loop_depth -= 1;
+                if let Some(increment) = increment {
+                    increment.resolve(resolver);
+                }
+            }
+            Stmt::Break(keyword) | Stmt::Continue(keyword) => {
+                if resolver.loop_depth == 0 {
+                    resolver.error(ResolverError::OutsideLoop(keyword.clone()));
+                }
             }
-            _ => Ok(()),
         }
     }
 }
 
 impl Expr {
-    fn resolve<'a>(&'a self, resolver: &mut Resolver<'a>) -> Result<()> {
+    fn resolve<'a>(&'a self, resolver: &mut Resolver<'a>) {
         match self {
             Expr::Assign { name, value } => {
-                value.resolve(resolver)?;
-                resolver.resolve_local(value, name)
+                value.resolve(resolver);
+                resolver.resolve_assignment(self, name)
             }
             Expr::Binary { left, right, .. } => {
-                left.resolve(resolver)?;
+                left.resolve(resolver);
                 right.resolve(resolver)
             }
             Expr::Call {
                 callee, arguments, ..
             } => {
-                callee.resolve(resolver)?;
+                callee.resolve(resolver);
                 for argument in arguments {
-                    argument.resolve(resolver)?;
+                    argument.resolve(resolver);
                 }
-                Ok(())
             }
             Expr::Get { object, .. } => object.resolve(resolver),
             Expr::Grouping { expression } => expression.resolve(resolver),
-            Expr::Literal { .. } => Ok(()),
+            Expr::Index { object, index, .. } => {
+                object.resolve(resolver);
+                index.resolve(resolver)
+            }
+            Expr::List { elements, .. } => {
+                for element in elements {
+                    element.resolve(resolver);
+                }
+            }
+            Expr::Literal { .. } => (),
             Expr::Logical { left, right, .. } => {
-                left.resolve(resolver)?;
+                left.resolve(resolver);
                 right.resolve(resolver)
             }
+            Expr::Lambda(function) => resolver.resolve_function(function, FunctionType::Function),
+            Expr::Conditional {
+                condition,
+                then_branch,
+                else_branch,
+                ..
+            } => {
+                condition.resolve(resolver);
+                then_branch.resolve(resolver);
+                else_branch.resolve(resolver);
+            }
+            Expr::Update { target, value, .. } => {
+                target.resolve(resolver);
+                value.resolve(resolver);
+            }
+            Expr::Map { entries, .. } => {
+                for (key, value) in entries {
+                    key.resolve(resolver);
+                    value.resolve(resolver);
+                }
+            }
             Expr::Set { object, value, .. } => {
-                value.resolve(resolver)?;
+                value.resolve(resolver);
                 object.resolve(resolver)
             }
-            Expr::Super { keyword, .. } => resolver.resolve_local(self, keyword),
+            Expr::SetIndex {
+                object,
+                index,
+                value,
+                ..
+            } => {
+                value.resolve(resolver);
+                object.resolve(resolver);
+                index.resolve(resolver)
+            }
+            Expr::Super { keyword, .. } => match resolver.current_class {
+                ClassType::None => {
+                    resolver.error(ResolverError::SuperOutsideClass(keyword.clone()))
+                }
+                ClassType::Class => {
+                    resolver.error(ResolverError::SuperWithoutSuperclass(keyword.clone()))
+                }
+                ClassType::Subclass => resolver.resolve_local(self, keyword),
+            },
             Expr::Unary { right, .. } => right.resolve(resolver),
             Expr::Variable { name } => {
                 if !resolver.is_empty() && resolver.get(&name.lexeme) == Some(false) {
-                    return Err(anyhow::anyhow!(
-                        "Can't read local variable in its own initializer"
-                    ));
+                    resolver.error(ResolverError::ReadInOwnInitializer(name.clone()));
                 }
 
                 resolver.resolve_local(self, name)
             }
             Expr::This { keyword } => {
                 if resolver.current_class == ClassType::None {
-                    return Err(anyhow::anyhow!("Can't use `this` outside of a class."));
+                    resolver.error(ResolverError::ThisOutsideClass(keyword.clone()));
                 }
                 resolver.resolve_local(self, keyword)
             }
@@ -1,5 +1,7 @@
+use unicode_ident::{is_xid_continue, is_xid_start};
+
 use crate::error::{Error, ScannerError, ScannerErrors};
-use crate::token::{Token, TokenType};
+use crate::token::{Span, Token, TokenType};
 
 type Result<T> = std::result::Result<T, ScannerError>;
 
@@ -8,9 +10,23 @@ pub struct Scanner {
     source: String,
     tokens: Vec<Token>,
 
+    /// Byte offset of the first character of the token being scanned.
     start: usize,
+    /// Byte offset of the next character to scan.
     current: usize,
     line: usize,
+    /// Column of the next character to scan, counted in characters.
+    column: usize,
+
+    start_line: usize,
+    start_column: usize,
+
+    /// The `${` being scanned, innermost last, with the number of `{` opened
+    /// since then.
+    interpolations: Vec<(Span, usize)>,
+    /// Emit whitespace and comments as tokens, so the source can be rebuilt
+    /// from them.
+    trivia: bool,
 }
 
 impl Scanner {
@@ -21,24 +37,43 @@ impl Scanner {
             start: 0,
             current: 0,
             line: 1,
+            column: 1,
+            start_line: 1,
+            start_column: 1,
+            interpolations: Vec::new(),
+            trivia: false,
         }
     }
 
+    /// Also emit `Whitespace` and `Comment` tokens, the lexemes of the tokens
+    /// then cover the whole source.
+    pub fn with_trivia(mut self) -> Self {
+        self.trivia = true;
+        self
+    }
+
     pub fn scan_tokens(mut self) -> std::result::Result<Vec<Token>, Error> {
         let mut errors = Vec::new();
 
         while !self.is_at_end() {
             self.start = self.current;
+            self.start_line = self.line;
+            self.start_column = self.column;
             if let Err(e) = self.scan_token() {
                 errors.push(e);
             }
         }
 
-        self.tokens.push(Token {
-            ty: TokenType::EoF,
-            lexeme: String::new(),
-            line: self.line,
-        });
+        errors.extend(
+            self.interpolations
+                .drain(..)
+                .map(|(span, _)| ScannerError::Interpolation(span)),
+        );
+
+        self.start = self.current;
+        self.start_line = self.line;
+        self.start_column = self.column;
+        self.add_token(TokenType::EoF);
 
         if errors.is_empty() {
             Ok(self.tokens)
@@ -48,7 +83,7 @@ impl Scanner {
     }
 
     fn is_at_end(&self) -> bool {
-        self.current >= self.source.chars().count()
+        self.current >= self.source.len()
     }
 
     fn scan_token(&mut self) -> Result<()> {
@@ -57,13 +92,40 @@ impl Scanner {
         match c {
             '(' => self.add_token(TokenType::LeftParen),
             ')' => self.add_token(TokenType::RightParen),
-            '{' => self.add_token(TokenType::LeftBrace),
-            '}' => self.add_token(TokenType::RightBrace),
+            '{' => {
+                if let Some((_, depth)) = self.interpolations.last_mut() {
+                    *depth += 1;
+                }
+                self.add_token(TokenType::LeftBrace)
+            }
+            '}' => match self.interpolations.last_mut() {
+                // the end of an interpolated expression, back to the string
+                Some((_, 0)) => {
+                    self.interpolations.pop();
+                    self.string()?;
+                }
+                Some((_, depth)) => {
+                    *depth -= 1;
+                    self.add_token(TokenType::RightBrace)
+                }
+                None => self.add_token(TokenType::RightBrace),
+            },
+            '[' => self.add_token(TokenType::LeftBracket),
+            ']' => self.add_token(TokenType::RightBracket),
+            ':' => self.add_token(TokenType::Colon),
+            '?' => self.add_token(TokenType::Question),
             ',' => self.add_token(TokenType::Comma),
             '.' => self.add_token(TokenType::Dot),
+            ';' => self.add_token(TokenType::Semicolon),
+
+            '-' if self.follow('-') => self.add_token(TokenType::MinusMinus),
+            '-' if self.follow('=') => self.add_token(TokenType::MinusEqual),
             '-' => self.add_token(TokenType::Minus),
+            '+' if self.follow('+') => self.add_token(TokenType::PlusPlus),
+            '+' if self.follow('=') => self.add_token(TokenType::PlusEqual),
             '+' => self.add_token(TokenType::Plus),
-            ';' => self.add_token(TokenType::Semicolon),
+            '*' if self.follow('*') => self.add_token(TokenType::StarStar),
+            '*' if self.follow('=') => self.add_token(TokenType::StarEqual),
             '*' => self.add_token(TokenType::Star),
 
             '!' if self.follow('=') => self.add_token(TokenType::BangEqual),
@@ -74,20 +136,45 @@ impl Scanner {
             '<' => self.add_token(TokenType::Less),
             '>' if self.follow('=') => self.add_token(TokenType::GreaterEqual),
             '>' => self.add_token(TokenType::Greater),
+            // `///` is a doc comment, but `////` is a plain comment
             '/' if self.follow('/') => {
-                while self.peek() != '\n' && !self.is_at_end() {
+                if self.peek() == '/' && self.peek_next() != '/' {
                     self.advance();
+                    self.doc_comment();
+                } else {
+                    while self.peek() != '\n' && !self.is_at_end() {
+                        self.advance();
+                    }
+                    self.add_trivia(TokenType::Comment);
                 }
             }
+            '/' if self.follow('*') => {
+                self.block_comment()?;
+                self.add_trivia(TokenType::Comment);
+            }
+            '/' if self.follow('=') => self.add_token(TokenType::SlashEqual),
+            '~' if self.follow('/') => self.add_token(TokenType::TildeSlash),
+            '%' => self.add_token(TokenType::Percent),
             '/' => self.add_token(TokenType::Slash),
-            '\n' => self.line += 1,
-            c if c.is_whitespace() => (),
+            c if c.is_whitespace() => {
+                while self.peek().is_whitespace() {
+                    self.advance();
+                }
+                self.add_trivia(TokenType::Whitespace);
+            }
 
+            '"' if self.follow('"') => {
+                if self.follow('"') {
+                    self.raw_string()?
+                } else {
+                    self.add_token(TokenType::String(String::new()))
+                }
+            }
             '"' => self.string()?,
             c if c.is_ascii_digit() => self.number()?,
-            c if c.is_ascii_alphabetic() || c == '_' => self.identifier(),
+            c if is_xid_start(c) || c == '_' => self.identifier(),
 
-            c => return Err(ScannerError::Character(c)),
+            c => return Err(ScannerError::Character(c, self.span())),
         }
 
         Ok(())
@@ -98,7 +185,7 @@ impl Scanner {
     }
 
     fn peek_next(&self) -> char {
-        self.source.chars().nth(self.current + 1).unwrap_or('\0')
+        self.source[self.current..].chars().nth(1).unwrap_or('\0')
     }
 
     fn follow(&mut self, c: char) -> bool {
@@ -112,15 +199,29 @@ impl Scanner {
     }
 
     fn advance(&mut self) -> Option<char> {
-        let c = self.source.chars().nth(self.current);
-        if c.is_some() {
-            self.current += 1;
+        let c = self.current()?;
+        self.current += c.len_utf8();
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
         }
-        c
+        Some(c)
     }
 
     fn current(&self) -> Option<char> {
-        self.source.chars().nth(self.current)
+        self.source[self.current..].chars().next()
+    }
+
+    /// The span of the token currently being scanned.
+    fn span(&self) -> Span {
+        Span {
+            offset: self.start,
+            len: self.current - self.start,
+            line: self.start_line,
+            column: self.start_column,
+        }
     }
 
     fn add_token(&mut self, token_type: TokenType) {
@@ -128,28 +229,138 @@ impl Scanner {
         self.tokens.push(Token {
             ty: token_type,
             lexeme: text.to_string(),
-            line: self.line,
+            span: self.span(),
         });
     }
 
+    fn add_trivia(&mut self, token_type: TokenType) {
+        if self.trivia {
+            self.add_token(token_type);
+        }
+    }
+
+    /// Scan the rest of a string, after its opening `"` or after the `}` of
+    /// an interpolation, up to its closing `"` or the next `${`.
     fn string(&mut self) -> Result<()> {
-        // TODO: remove the double peek
-        while self.peek() != '"' && !self.is_at_end() {
-            if self.peek() == '\n' {
-                self.line += 1;
+        let mut value = String::new();
+        // keep scanning after a bad escape so the rest of the string isn't
+        // reported as code
+        let mut error = None;
+
+        loop {
+            match self.advance() {
+                None => return Err(ScannerError::String(self.span())),
+                Some('"') => break,
+                Some('$') if self.follow('{') => {
+                    let span = Span {
+                        offset: self.current - 2,
+                        len: 2,
+                        line: self.line,
+                        column: self.column - 2,
+                    };
+                    self.interpolations.push((span, 0));
+                    self.add_token(TokenType::Interpolation(value));
+                    return error.map_or(Ok(()), Err);
+                }
+                Some('\\') => match self.escape() {
+                    Ok(c) => value.push(c),
+                    Err(e) => {
+                        error.get_or_insert(e);
+                    }
+                },
+                Some(c) => value.push(c),
             }
-            self.advance();
         }
 
-        if self.is_at_end() {
-            return Err(ScannerError::String)?;
+        self.add_token(TokenType::String(value));
+        error.map_or(Ok(()), Err)
+    }
+
+    /// Decode the escape sequence following a `\\`.
+    fn escape(&mut self) -> Result<char> {
+        // the `\` has already been consumed, and isn't a line break
+        let (start, line, column) = (self.current - 1, self.line, self.column - 1);
+        let span = |scanner: &Self| Span {
+            offset: start,
+            len: scanner.current - start,
+            line,
+            column,
+        };
+
+        let c = match self.advance() {
+            Some('n') => '\n',
+            Some('t') => '\t',
+            Some('r') => '\r',
+            Some('0') => '\0',
+            Some('\\') => '\\',
+            Some('"') => '"',
+            Some('$') => '$',
+            Some('u') => {
+                let mut digits = String::new();
+                let closed = self.follow('{') && {
+                    while self.peek().is_ascii_hexdigit() && digits.len() < 6 {
+                        digits.extend(self.advance());
+                    }
+                    self.follow('}')
+                };
+                let c = u32::from_str_radix(&digits, 16)
+                    .ok()
+                    .and_then(char::from_u32)
+                    .filter(|_| closed);
+                let text = self.source[start..self.current].to_string();
+                return c.ok_or_else(|| ScannerError::UnicodeEscape(text, span(self)));
+            }
+            Some('\n') => return Err(ScannerError::Escape(String::from("\\"), span(self))),
+            Some(c) => return Err(ScannerError::Escape(format!("\\{c}"), span(self))),
+            // the string itself is reported as unterminated
+            None => return Err(ScannerError::Escape(String::from("\\"), span(self))),
+        };
+        Ok(c)
+    }
+
+    fn doc_comment(&mut self) {
+        let mut text = String::new();
+        while self.peek() != '\n' && !self.is_at_end() {
+            text.extend(self.advance());
+        }
+
+        let text = text.strip_prefix(' ').unwrap_or(&text).trim_end();
+        self.add_token(TokenType::DocComment(text.to_string()));
+    }
+
+    /// Skip a `/* */` comment, which can contain other block comments.
+    fn block_comment(&mut self) -> Result<()> {
+        let mut depth = 1;
+
+        while depth > 0 {
+            match self.advance() {
+                None => return Err(ScannerError::Comment(self.span())),
+                Some('/') if self.follow('*') => depth += 1,
+                Some('*') if self.follow('/') => depth -= 1,
+                Some(_) => (),
+            }
         }
 
-        self.advance(); // skip the closing `"`
+        Ok(())
+    }
+
+    /// Scan a `"""` string, taken verbatim up to the closing `"""`.
+    fn raw_string(&mut self) -> Result<()> {
+        let mut value = String::new();
+
+        loop {
+            match self.advance() {
+                None => return Err(ScannerError::String(self.span())),
+                Some('"') if self.peek() == '"' && self.peek_next() == '"' => {
+                    self.advance();
+                    self.advance();
+                    break;
+                }
+                Some(c) => value.push(c),
+            }
+        }
 
-        // skip the starting `"`
-        let usr_str = self.source[self.start + 1..self.current - 1].to_string();
-        self.add_token(TokenType::String(usr_str));
+        self.add_token(TokenType::String(value));
         Ok(())
     }
 
@@ -169,14 +380,14 @@ impl Scanner {
         let number = &self.source[self.start..self.current];
         let number = number
             .parse()
-            .map_err(|e| ScannerError::Number(number.to_string(), e))?;
+            .map_err(|e| ScannerError::Number(number.to_string(), e, self.span()))?;
         self.add_token(TokenType::Number(number));
 
         Ok(())
     }
 
     fn identifier(&mut self) {
-        while self.peek().is_ascii_alphanumeric() {
+        while is_xid_continue(self.peek()) {
             self.advance();
         }
 
@@ -2,14 +2,18 @@ use std::rc::Rc;
 
 use crate::{callable::Function, expr::Expr, token::Token};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Stmt {
     Block(Rc<Vec<Stmt>>),
+    Break(Token),
     Class {
         name: Token,
         superclass: Option<Expr>,
         methods: Vec<Function>,
+        /// The `///` comments right before the class.
+        doc: Option<String>,
     },
+    Continue(Token),
     Expression(Expr),
     Function(Function),
     If {
@@ -17,7 +21,10 @@ pub enum Stmt {
         then_branch: Box<Stmt>,
         else_branch: Option<Box<Stmt>>,
     },
-    Print(Expr),
+    Print {
+        keyword: Token,
+        value: Expr,
+    },
     Return {
         keyword: Token,
         value: Option<Expr>,
@@ -29,9 +36,36 @@ pub enum Stmt {
     While {
         condition: Expr,
         body: Box<Stmt>,
+        /// The increment of a desugared `for`, run after the body even when
+        /// it `continue`s.
+        increment: Option<Expr>,
     },
 }
 
+impl Stmt {
+    /// A token of this statement, its keyword or name when the AST keeps one,
+    /// for warnings about the whole statement to point at.
+    pub fn token(&self) -> Option<&Token> {
+        match self {
+            Self::Block(stmts) => stmts.iter().find_map(Stmt::token),
+            Self::Class { name, .. }
+            | Self::Function(Function { name, .. })
+            | Self::Var { name, .. } => Some(name),
+            Self::Break(keyword)
+            | Self::Continue(keyword)
+            | Self::Print { keyword, .. }
+            | Self::Return { keyword, .. } => Some(keyword),
+            Self::Expression(expr)
+            | Self::If {
+                condition: expr, ..
+            }
+            | Self::While {
+                condition: expr, ..
+            } => expr.token(),
+        }
+    }
+}
+
 impl Default for Stmt {
     fn default() -> Self {
         Stmt::Expression(Expr::default())
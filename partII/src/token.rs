@@ -1,4 +1,4 @@
-use std::fmt::Display;
+use std::{fmt::Display, ops::Range};
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum TokenType {
@@ -7,6 +7,10 @@ pub enum TokenType {
     RightParen,
     LeftBrace,
     RightBrace,
+    LeftBracket,
+    RightBracket,
+    Colon,
+    Question,
     Comma,
     Dot,
     Minus,
@@ -14,6 +18,7 @@ pub enum TokenType {
     Semicolon,
     Slash,
     Star,
+    Percent,
     // One or two characters tokens
     Bang,
     BangEqual,
@@ -23,13 +28,31 @@ pub enum TokenType {
     GreaterEqual,
     Less,
     LessEqual,
+    PlusEqual,
+    MinusEqual,
+    StarEqual,
+    SlashEqual,
+    PlusPlus,
+    MinusMinus,
+    StarStar,
+    TildeSlash,
     // Literals
     Identifier(String),
     String(String),
+    /// The part of a string before a `${`, the interpolated expression and
+    /// the rest of the string follow.
+    Interpolation(String),
     Number(f64),
+    /// A `///` comment line, without the slashes.
+    DocComment(String),
+    // Trivia, only kept when scanning for the concrete syntax tree
+    Whitespace,
+    Comment,
     // Keywords
     And,
+    Break,
     Class,
+    Continue,
     Else,
     False,
     Fun,
@@ -101,15 +124,58 @@ impl std::hash::Hash for TokenType {
             TokenType::Var => state.write_u8(36),
             TokenType::While => state.write_u8(37),
             TokenType::EoF => state.write_u8(38),
+            TokenType::LeftBracket => state.write_u8(39),
+            TokenType::RightBracket => state.write_u8(40),
+            TokenType::Colon => state.write_u8(41),
+            TokenType::Break => state.write_u8(42),
+            TokenType::Continue => state.write_u8(43),
+            TokenType::Question => state.write_u8(44),
+            TokenType::PlusEqual => state.write_u8(45),
+            TokenType::MinusEqual => state.write_u8(46),
+            TokenType::StarEqual => state.write_u8(47),
+            TokenType::SlashEqual => state.write_u8(48),
+            TokenType::PlusPlus => state.write_u8(49),
+            TokenType::MinusMinus => state.write_u8(50),
+            TokenType::Percent => state.write_u8(51),
+            TokenType::StarStar => state.write_u8(52),
+            TokenType::TildeSlash => state.write_u8(53),
+            TokenType::Interpolation(s) => {
+                state.write_u8(54);
+                s.hash(state);
+            }
+            TokenType::DocComment(s) => {
+                state.write_u8(55);
+                s.hash(state);
+            }
+            TokenType::Whitespace => state.write_u8(56),
+            TokenType::Comment => state.write_u8(57),
         }
     }
 }
 
 impl TokenType {
+    /// Tokens which aren't part of the grammar: whitespace and comments.
+    pub fn is_trivia(&self) -> bool {
+        matches!(self, Self::Whitespace | Self::Comment | Self::DocComment(_))
+    }
+
+    /// The binary operator applied by a compound assignment or an increment.
+    pub fn compound_operator(&self) -> Option<Self> {
+        match self {
+            Self::PlusEqual | Self::PlusPlus => Some(Self::Plus),
+            Self::MinusEqual | Self::MinusMinus => Some(Self::Minus),
+            Self::StarEqual => Some(Self::Star),
+            Self::SlashEqual => Some(Self::Slash),
+            _ => None,
+        }
+    }
+
     pub fn from_keyword(keyword: &str) -> Option<Self> {
         let keyword = match keyword {
             "and" => Self::And,
+            "break" => Self::Break,
             "class" => Self::Class,
+            "continue" => Self::Continue,
             "else" => Self::Else,
             "false" => Self::False,
             "fun" => Self::Fun,
@@ -130,11 +196,35 @@ impl TokenType {
     }
 }
 
+/// Location of a token in the source code.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Span {
+    /// Byte offset of the first character of the token.
+    pub offset: usize,
+    /// Length of the token in bytes.
+    pub len: usize,
+    /// Line of the first character of the token, starting at 1.
+    pub line: usize,
+    /// Column of the first character of the token, starting at 1.
+    pub column: usize,
+}
+
+impl Span {
+    /// The columns covered by the token on its first line.
+    pub fn columns(&self, source: &str) -> Range<usize> {
+        let text = source
+            .get(self.offset..self.offset + self.len)
+            .unwrap_or("");
+        let width = text.lines().next().unwrap_or("").chars().count();
+        self.column..self.column + width.max(1)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Token {
     pub ty: TokenType,
     pub lexeme: String,
-    pub line: usize,
+    pub span: Span,
 }
 
 impl Display for Token {
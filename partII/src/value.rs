@@ -1,6 +1,12 @@
 use anyhow::anyhow;
+use indexmap::IndexMap;
 
-use std::{fmt::Display, rc::Rc};
+use std::{
+    cell::RefCell,
+    fmt::Display,
+    hash::{Hash, Hasher},
+    rc::Rc,
+};
 
 use crate::{
     callable::{Callable, Function},
@@ -9,27 +15,31 @@ use crate::{
     instance::Instance,
 };
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub enum Value {
     Callable(Rc<dyn Callable>),
-    Class(Class),
+    Class(Rc<Class>),
     Instance(Instance),
+    List(Rc<RefCell<Vec<Value>>>),
+    /// Keep the insertion order so iterating on a map is deterministic.
+    Map(Rc<RefCell<IndexMap<Value, Value>>>),
     String(String),
     Number(f64),
     Bool(bool),
+    #[default]
     Nil,
 }
 
-impl Default for Value {
-    fn default() -> Self {
-        Self::Nil
-    }
-}
-
+/// Strings, numbers, booleans and nil are compared by value, everything else
+/// is only equal to itself.
 impl PartialEq for Value {
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {
-            (Self::Callable(left), Self::Callable(right)) => Rc::as_ptr(left) == Rc::as_ptr(right),
+            (Self::Callable(left), Self::Callable(right)) => Rc::ptr_eq(left, right),
+            (Self::Class(left), Self::Class(right)) => Rc::ptr_eq(left, right),
+            (Self::Instance(left), Self::Instance(right)) => left.ptr_eq(right),
+            (Self::List(left), Self::List(right)) => Rc::ptr_eq(left, right),
+            (Self::Map(left), Self::Map(right)) => Rc::ptr_eq(left, right),
             (Self::String(left), Self::String(right)) => left == right,
             (Self::Number(left), Self::Number(right)) => left == right,
             (Self::Bool(left), Self::Bool(right)) => left == right,
@@ -41,6 +51,25 @@ impl PartialEq for Value {
 
 impl Eq for Value {}
 
+impl Hash for Value {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match self {
+            Self::Callable(fun) => Rc::as_ptr(fun).cast::<()>().hash(state),
+            Self::Class(class) => Rc::as_ptr(class).hash(state),
+            Self::Instance(instance) => instance.as_ptr().hash(state),
+            Self::List(list) => Rc::as_ptr(list).hash(state),
+            Self::Map(map) => Rc::as_ptr(map).hash(state),
+            Self::String(s) => s.hash(state),
+            // `0.0 == -0.0` so they must have the same hash
+            Self::Number(n) if *n == 0.0 => 0.0_f64.to_bits().hash(state),
+            Self::Number(n) => n.to_bits().hash(state),
+            Self::Bool(b) => b.hash(state),
+            Self::Nil => (),
+        }
+    }
+}
+
 impl Value {
     pub fn is_truthy(&self) -> bool {
         !self.is_falsy()
@@ -66,6 +95,23 @@ impl Value {
         matches!(self, Self::String(_))
     }
 
+    pub fn is_list(&self) -> bool {
+        matches!(self, Self::List(_))
+    }
+
+    pub fn is_map(&self) -> bool {
+        matches!(self, Self::Map(_))
+    }
+
+    /// Only immutable values can be used as map keys. NaN isn't equal to
+    /// itself so an entry with a NaN key could never be found again.
+    pub fn is_hashable(&self) -> bool {
+        match self {
+            Self::Number(n) => !n.is_nan(),
+            value => matches!(value, Self::String(_) | Self::Bool(_) | Self::Nil),
+        }
+    }
+
     pub fn is_callable(&self) -> bool {
         matches!(self, Self::Callable { .. })
     }
@@ -84,6 +130,83 @@ impl Value {
         }
     }
 
+    pub fn list(self) -> Result<Rc<RefCell<Vec<Value>>>, RuntimeError> {
+        match self {
+            Self::List(list) => Ok(list),
+            _ => Err(anyhow!("Expected `list` but instead got {:?}", self))?,
+        }
+    }
+
+    pub fn map(self) -> Result<Rc<RefCell<IndexMap<Value, Value>>>, RuntimeError> {
+        match self {
+            Self::Map(map) => Ok(map),
+            _ => Err(anyhow!("Expected `map` but instead got {:?}", self))?,
+        }
+    }
+
+    /// Check the value can be used as a map key.
+    pub fn key(self) -> Result<Self, RuntimeError> {
+        if self.is_hashable() {
+            Ok(self)
+        } else {
+            Err(anyhow!(
+                "Map keys must be strings, numbers other than NaN, booleans or nil, got {}.",
+                self
+            ))?
+        }
+    }
+
+    /// Convert the value to an index in a list of `len` elements.
+    pub fn index(self, len: usize) -> Result<usize, RuntimeError> {
+        let index = self.number()?;
+        if index.fract() != 0.0 || index < 0.0 {
+            Err(anyhow!(
+                "List index must be a non-negative integer, got {}.",
+                index
+            ))?
+        } else if index as usize >= len {
+            Err(anyhow!(
+                "Index {} out of range for a list of length {}.",
+                index,
+                len
+            ))?
+        } else {
+            Ok(index as usize)
+        }
+    }
+
+    /// Read `object[index]` in a list or a map.
+    pub fn get_index(&self, index: &Value) -> Result<Value, RuntimeError> {
+        match self {
+            Self::List(list) => {
+                let list = list.borrow();
+                Ok(list[index.clone().index(list.len())?].clone())
+            }
+            Self::Map(map) => Ok(map
+                .borrow()
+                .get(index)
+                .ok_or_else(|| anyhow!("Key `{}` not found in map.", index))?
+                .clone()),
+            _ => Err(anyhow!("Only lists and maps can be indexed."))?,
+        }
+    }
+
+    /// Write `object[index] = value` in a list or a map.
+    pub fn set_index(&self, index: Value, value: Value) -> Result<(), RuntimeError> {
+        match self {
+            Self::List(list) => {
+                let mut list = list.borrow_mut();
+                let index = index.index(list.len())?;
+                list[index] = value;
+            }
+            Self::Map(map) => {
+                map.borrow_mut().insert(index.key()?, value);
+            }
+            _ => Err(anyhow!("Only lists and maps can be indexed."))?,
+        }
+        Ok(())
+    }
+
     pub fn bool(self) -> Result<bool, RuntimeError> {
         match self {
             Self::Bool(b) => Ok(b),
@@ -91,7 +214,7 @@ impl Value {
         }
     }
 
-    pub fn class(self) -> Result<Class, RuntimeError> {
+    pub fn class(self) -> Result<Rc<Class>, RuntimeError> {
         match self {
             Self::Class(class) => Ok(class),
             _ => Err(anyhow!("Expected `class` but instead got {:?}", self))?,
@@ -137,6 +260,12 @@ impl From<bool> for Value {
     }
 }
 
+impl From<Vec<Value>> for Value {
+    fn from(list: Vec<Value>) -> Self {
+        Self::List(Rc::new(RefCell::new(list)))
+    }
+}
+
 impl From<Rc<dyn Callable>> for Value {
     fn from(fun: Rc<dyn Callable>) -> Self {
         Self::Callable(fun)
@@ -145,10 +274,22 @@ impl From<Rc<dyn Callable>> for Value {
 
 impl From<Class> for Value {
     fn from(class: Class) -> Self {
+        Self::Class(Rc::new(class))
+    }
+}
+
+impl From<Rc<Class>> for Value {
+    fn from(class: Rc<Class>) -> Self {
         Self::Class(class)
     }
 }
 
+impl From<IndexMap<Value, Value>> for Value {
+    fn from(map: IndexMap<Value, Value>) -> Self {
+        Self::Map(Rc::new(RefCell::new(map)))
+    }
+}
+
 impl From<Function> for Value {
     fn from(fun: Function) -> Self {
         Self::Callable(Rc::new(fun))
@@ -163,14 +304,77 @@ impl From<Instance> for Value {
 
 impl Display for Value {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.write(f, &mut Vec::new())
+    }
+}
+
+impl Value {
+    /// `printing` holds the lists and maps being written around this value, a
+    /// list or a map containing itself is written `[...]` or `{...}` instead of
+    /// recursing forever.
+    fn write(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+        printing: &mut Vec<*const ()>,
+    ) -> std::fmt::Result {
         match self {
             Self::Callable { .. } => write!(f, "fun"),
             Self::Class { .. } => write!(f, "class"),
             Self::Instance(i) => write!(f, "{}", i),
+            Self::List(list) => {
+                let ptr = Rc::as_ptr(list) as *const ();
+                if printing.contains(&ptr) {
+                    return write!(f, "[...]");
+                }
+                printing.push(ptr);
+
+                write!(f, "[")?;
+                for (idx, value) in list.borrow().iter().enumerate() {
+                    if idx > 0 {
+                        write!(f, ", ")?;
+                    }
+                    value.fmt_nested(f, printing)?;
+                }
+
+                printing.pop();
+                write!(f, "]")
+            }
+            Self::Map(map) => {
+                let ptr = Rc::as_ptr(map) as *const ();
+                if printing.contains(&ptr) {
+                    return write!(f, "{{...}}");
+                }
+                printing.push(ptr);
+
+                write!(f, "{{")?;
+                for (idx, (key, value)) in map.borrow().iter().enumerate() {
+                    if idx > 0 {
+                        write!(f, ", ")?;
+                    }
+                    key.fmt_nested(f, printing)?;
+                    write!(f, ": ")?;
+                    value.fmt_nested(f, printing)?;
+                }
+
+                printing.pop();
+                write!(f, "}}")
+            }
             Self::String(s) => write!(f, "{}", s),
             Self::Number(n) => write!(f, "{}", n),
             Self::Bool(b) => write!(f, "{}", b),
             Self::Nil => write!(f, "nil"),
         }
     }
+
+    /// Display a value inside a list or a map, strings are quoted.
+    fn fmt_nested(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+        printing: &mut Vec<*const ()>,
+    ) -> std::fmt::Result {
+        match self {
+            Self::String(s) => write!(f, "{:?}", s),
+            value => value.write(f, printing),
+        }
+    }
 }
@@ -0,0 +1,52 @@
+//! Parse every code sample into a concrete syntax tree, and check that it
+//! prints back the source and keeps the tokens `Parser::parse` reads.
+
+use std::path::Path;
+
+use lox::{parser::Parser, scanner::Scanner};
+
+/// Samples that are only there to be scanned, they aren't valid Lox.
+const NOT_PARSED: &[&str] = &["first.lox"];
+
+fn check_sample(path: &Path) {
+    let name = path.file_name().unwrap().to_string_lossy();
+    let source = std::fs::read_to_string(path).unwrap();
+
+    let tokens = Scanner::new(source.clone())
+        .with_trivia()
+        .scan_tokens()
+        .unwrap_or_else(|error| panic!("{name}: {error}"));
+    let tree = Parser::new(tokens)
+        .parse_cst()
+        .unwrap_or_else(|error| panic!("{name}: {error}"));
+    assert_eq!(tree.to_string(), source, "{name} doesn't print back");
+
+    let tokens = Scanner::new(source).scan_tokens().unwrap();
+    let ast = Parser::new(tokens).parse().unwrap();
+    // printing back only checks the lexemes, the types and spans of the
+    // tokens must have made it into the tree too: the tokens come from the
+    // same source, so even their spans are the same
+    let reparsed = tree.reparse().unwrap();
+    assert_eq!(reparsed, ast, "{name}");
+}
+
+#[test]
+fn samples() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("code_samples");
+    let mut checked = 0;
+
+    for entry in std::fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        let name = path.file_name().unwrap().to_string_lossy();
+        if NOT_PARSED.contains(&name.as_ref()) {
+            let source = std::fs::read_to_string(&path).unwrap();
+            let tokens = Scanner::new(source).scan_tokens().unwrap();
+            assert!(Parser::new(tokens).parse().is_err(), "{name} parses now");
+            continue;
+        }
+        check_sample(&path);
+        checked += 1;
+    }
+
+    assert!(checked > 0);
+}
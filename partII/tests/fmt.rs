@@ -0,0 +1,110 @@
+use std::path::Path;
+
+use lox::{
+    formatter::{format, FormatOptions},
+    parser::Parser,
+    scanner::Scanner,
+    stmt::Stmt,
+    token::Span,
+};
+
+/// Samples that are only there to be scanned, they aren't valid Lox.
+const NOT_PARSED: &[&str] = &["first.lox"];
+
+/// The AST of a script, without the positions of its tokens.
+fn ast(source: &str) -> Vec<Stmt> {
+    let mut tokens = Scanner::new(source.to_string()).scan_tokens().unwrap();
+    for token in &mut tokens {
+        token.span = Span::default();
+    }
+    Parser::new(tokens).parse().unwrap()
+}
+
+fn check_sample(path: &Path) {
+    let name = path.file_name().unwrap().to_string_lossy();
+    let source = std::fs::read_to_string(path).unwrap();
+    let expected = ast(&source);
+    let narrow = FormatOptions {
+        indent: 2,
+        line_length: 20,
+    };
+
+    for options in [FormatOptions::default(), narrow] {
+        let formatted = format(&source, options).unwrap();
+        assert_eq!(
+            ast(&formatted),
+            expected,
+            "{name} changed meaning once formatted:\n{formatted}"
+        );
+        assert_eq!(
+            format(&formatted, options).unwrap(),
+            formatted,
+            "{name} isn't formatted the same twice"
+        );
+    }
+}
+
+#[test]
+fn code_samples_round_trip() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("code_samples");
+    let mut checked = 0;
+
+    for entry in std::fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        let name = path.file_name().unwrap().to_string_lossy();
+        if NOT_PARSED.contains(&name.as_ref()) {
+            let source = std::fs::read_to_string(&path).unwrap();
+            let tokens = Scanner::new(source).scan_tokens().unwrap();
+            assert!(Parser::new(tokens).parse().is_err(), "{name} parses now");
+            continue;
+        }
+        check_sample(&path);
+        checked += 1;
+    }
+
+    assert!(checked > 0);
+}
+
+#[test]
+fn trailing_comments_stay_on_their_line() {
+    let source = "\
+var xs = [
+    1, // first
+    2 // second
+];
+foo(
+    a, // the a
+    b
+);
+var total = 1 + // left
+    2;
+print total; // after print
+";
+    let formatted = format(source, FormatOptions::default()).unwrap();
+
+    for line in ["1, // first", "2 // second", "a, // the a", "1 + // left"] {
+        assert!(
+            formatted.lines().any(|l| l.trim_end().ends_with(line)),
+            "`{line}` was split:\n{formatted}"
+        );
+    }
+    assert!(formatted.contains("print total; // after print"));
+}
+
+#[test]
+fn unary_operators_are_separated_only_when_they_glue() {
+    let source = "print !!true;\nprint - -x;\nprint - --x;\nprint -!x;\n";
+    assert_eq!(
+        format(source, FormatOptions::default()).unwrap(),
+        "print !!true;\nprint - -x;\nprint - --x;\nprint -!x;\n"
+    );
+}
+
+#[test]
+fn comments_before_a_comma_stick_to_the_item() {
+    let source = "f(a/* first */ , b);\nvar m = {\"k\"/* key */ : 1};\nprint a/* c */ ;\n";
+    assert_eq!(
+        format(source, FormatOptions::default()).unwrap(),
+        "f(a /* first */, b);\nvar m = {\"k\" /* key */: 1};\nprint a /* c */;\n"
+    );
+}
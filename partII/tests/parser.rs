@@ -0,0 +1,88 @@
+//! Parse scripts and check the doc comments attached to their declarations.
+
+use lox::{parser::Parser, scanner::Scanner, stmt::Stmt};
+
+fn parse(source: &str) -> Vec<Stmt> {
+    let tokens = Scanner::new(source.to_string()).scan_tokens().unwrap();
+    Parser::new(tokens).parse().unwrap()
+}
+
+/// The doc of every function and class declared at the top level.
+fn docs(source: &str) -> Vec<Option<String>> {
+    parse(source)
+        .into_iter()
+        .filter_map(|stmt| match stmt {
+            Stmt::Function(function) => Some(function.doc),
+            Stmt::Class { doc, .. } => Some(doc),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn function_doc() {
+    let source = "\
+/// Add two numbers.
+///
+///   Indented lines keep their indentation.
+fun add(a, b) {
+    return a + b;
+}
+
+fun undocumented() {}
+";
+    assert_eq!(
+        docs(source),
+        [
+            Some("Add two numbers.\n\n  Indented lines keep their indentation.".to_string()),
+            None
+        ]
+    );
+}
+
+#[test]
+fn class_and_method_docs() {
+    let source = "\
+/// A greeter.
+class Greeter {
+    /// Say hi to `name`.
+    greet(name) {
+        return \"hi \" + name;
+    }
+
+    bye() {}
+}
+";
+    let stmts = parse(source);
+    let [Stmt::Class { doc, methods, .. }] = stmts.as_slice() else {
+        panic!("expected a single class, got {stmts:?}");
+    };
+    assert_eq!(doc.as_deref(), Some("A greeter."));
+
+    let method_docs: Vec<_> = methods.iter().map(|method| method.doc.as_deref()).collect();
+    assert_eq!(method_docs, [Some("Say hi to `name`."), None]);
+}
+
+#[test]
+fn four_slashes_are_not_a_doc() {
+    let source = "\
+//// A plain comment.
+fun plain() {}
+
+//// A plain comment,
+/// then a doc.
+fun documented() {}
+";
+    assert_eq!(docs(source), [None, Some("then a doc.".to_string())]);
+}
+
+#[test]
+fn doc_before_a_statement_is_dropped() {
+    // the doc belongs to the `print`, it doesn't carry over to the function
+    let source = "\
+/// Not a declaration.
+print 1;
+fun f() {}
+";
+    assert_eq!(docs(source), [None]);
+}
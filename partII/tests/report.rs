@@ -0,0 +1,94 @@
+//! Run failing scripts and check the report printed on stderr: the snippet
+//! pointing at the error and the Lox call stack.
+
+use std::process::Command;
+
+/// Run `source` saved as `name`, and return what it printed on stderr.
+fn report(name: &str, source: &str) -> String {
+    let dir = env!("CARGO_TARGET_TMPDIR");
+    std::fs::write(format!("{dir}/{name}"), source).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_partII"))
+        .current_dir(dir)
+        .arg(name)
+        .output()
+        .unwrap();
+    assert_eq!(
+        output.status.code(),
+        Some(70),
+        "{name} didn't fail at runtime"
+    );
+    String::from_utf8(output.stderr).unwrap()
+}
+
+#[test]
+fn top_level_error() {
+    let report = report("top_level_error.lox", "var a = 1;\nprint \"a\" - a;\n");
+    assert_eq!(
+        report,
+        "\
+error: Expected `number` but instead got String(\"a\")
+ --> top_level_error.lox:2:11
+  |
+2 | print \"a\" - a;
+  |           ^
+[line 2] in script
+"
+    );
+}
+
+#[test]
+fn nested_call_error() {
+    let source = "\
+fun inner(x) {
+    return x + nil;
+}
+
+fun outer() {
+    return inner(1);
+}
+
+print outer();
+";
+    assert_eq!(
+        report("nested_call_error.lox", source),
+        "\
+error: Expected `number` but instead got Nil
+ --> nested_call_error.lox:2:14
+  |
+2 |     return x + nil;
+  |              ^
+[line 2] in inner()
+[line 6] in outer()
+[line 9] in script
+"
+    );
+}
+
+#[test]
+fn call_error_has_no_frame() {
+    // the arity is checked before entering `pair`, so it's not on the stack
+    let source = "\
+fun pair(a, b) {
+    return a + b;
+}
+
+fun first() {
+    return pair(1);
+}
+
+first();
+";
+    assert_eq!(
+        report("call_error_has_no_frame.lox", source),
+        "\
+error: Expected 2 arguments but got 1.
+ --> call_error_has_no_frame.lox:6:18
+  |
+6 |     return pair(1);
+  |                  ^
+[line 6] in first()
+[line 9] in script
+"
+    );
+}
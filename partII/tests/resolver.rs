@@ -0,0 +1,79 @@
+//! Resolve scripts and check the warnings the resolver reports.
+
+use lox::{interpreter::Interpreter, parser::Parser, resolver::Resolver, scanner::Scanner};
+
+/// The warnings for `source`, with the line they point at.
+fn warnings(source: &str) -> Vec<(usize, String)> {
+    let tokens = Scanner::new(source.to_string()).scan_tokens().unwrap();
+    let stmts = Parser::new(tokens).parse().unwrap();
+
+    let mut interpreter = Interpreter::new();
+    let mut resolver = Resolver::new(&mut interpreter);
+    resolver.resolve(&stmts).unwrap();
+    resolver
+        .warnings()
+        .0
+        .iter()
+        .map(|warning| (warning.token().span.line, warning.to_string()))
+        .collect()
+}
+
+fn shadows(line: usize, name: &str) -> (usize, String) {
+    let message = format!("Local variable `{name}` shadows an outer binding.");
+    (line, message)
+}
+
+#[test]
+fn local_shadows_local() {
+    let source = "\
+fun f() {
+    var a = 1;
+    {
+        var a = 2;
+        print a;
+    }
+    print a;
+}
+";
+    assert_eq!(warnings(source), [shadows(4, "a")]);
+}
+
+#[test]
+fn local_shadows_global() {
+    let source = "\
+var g = 1;
+fun f() {
+    var g = 2;
+    print g;
+}
+";
+    assert_eq!(warnings(source), [shadows(3, "g")]);
+}
+
+#[test]
+fn parameter_shadows_global() {
+    let source = "\
+fun g() {}
+fun f(g) {
+    print g;
+}
+";
+    assert_eq!(warnings(source), [shadows(2, "g")]);
+}
+
+#[test]
+fn local_shadows_native() {
+    let source = "\
+{
+    var clock = 0;
+    print clock;
+}
+";
+    assert_eq!(warnings(source), [shadows(2, "clock")]);
+}
+
+#[test]
+fn globals_dont_shadow() {
+    // redeclaring a global replaces it, there's no outer binding
+    assert_eq!(warnings("var g = 1;\nvar g = 2;\nprint g;\n"), []);
+}
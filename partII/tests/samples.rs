@@ -0,0 +1,124 @@
+//! Run code samples and compare what they print with their `// expect: `
+//! comments.
+
+use std::process::Command;
+
+/// The lines a sample should print, in order.
+fn expected(source: &str) -> Vec<&str> {
+    source
+        .lines()
+        .filter_map(|line| line.split_once("// expect: ").map(|(_, expect)| expect))
+        .collect()
+}
+
+fn run_sample(name: &str) {
+    let path = format!("{}/code_samples/{name}", env!("CARGO_MANIFEST_DIR"));
+    let source = std::fs::read_to_string(&path).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_partII"))
+        .arg(&path)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{name} failed:\n{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(
+        stdout.lines().collect::<Vec<_>>(),
+        expected(&source),
+        "{name}"
+    );
+}
+
+#[test]
+fn simple() {
+    run_sample("simple.lox");
+}
+
+#[test]
+fn function() {
+    run_sample("function.lox");
+}
+
+#[test]
+fn recursive_function() {
+    run_sample("recursive_function.lox");
+}
+
+#[test]
+fn bug() {
+    run_sample("bug.lox");
+}
+
+#[test]
+fn closure() {
+    run_sample("closure.lox");
+}
+
+#[test]
+fn instance() {
+    run_sample("instance.lox");
+}
+
+#[test]
+fn scope() {
+    run_sample("scope.lox");
+}
+
+#[test]
+fn list() {
+    run_sample("list.lox");
+}
+
+#[test]
+fn map() {
+    run_sample("map.lox");
+}
+
+#[test]
+fn loop_control() {
+    run_sample("loop_control.lox");
+}
+
+#[test]
+fn equality() {
+    run_sample("equality.lox");
+}
+
+#[test]
+fn lambda() {
+    run_sample("lambda.lox");
+}
+
+#[test]
+fn conditional() {
+    run_sample("conditional.lox");
+}
+
+#[test]
+fn update() {
+    run_sample("update.lox");
+}
+
+#[test]
+fn arithmetic() {
+    run_sample("arithmetic.lox");
+}
+
+#[test]
+fn string() {
+    run_sample("string.lox");
+}
+
+#[test]
+fn comments() {
+    run_sample("comments.lox");
+}
+
+#[test]
+fn unicode() {
+    run_sample("unicode.lox");
+}
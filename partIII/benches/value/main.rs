@@ -0,0 +1,141 @@
+//! Compare the tagged `Value` enum with a NaN-boxed layout, on a loop doing
+//! what the VM does with its stack: arithmetic, comparisons, truthiness and
+//! equality on mixed types.
+//!
+//! ```text
+//! cargo bench --bench value
+//! ```
+
+use clox::value::Value;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+mod nan_box;
+
+use nan_box::NanBoxed;
+
+/// The operations the VM needs from a value layout.
+trait Layout: Clone {
+    fn number(number: f64) -> Self;
+    fn boolean(boolean: bool) -> Self;
+    fn nil() -> Self;
+    fn as_number(&self) -> Option<f64>;
+    fn is_falsey(&self) -> bool;
+    fn equals(&self, other: &Self) -> bool;
+}
+
+impl Layout for Value {
+    fn number(number: f64) -> Self {
+        Value::Number(number)
+    }
+
+    fn boolean(boolean: bool) -> Self {
+        Value::Bool(boolean)
+    }
+
+    fn nil() -> Self {
+        Value::Nil
+    }
+
+    fn as_number(&self) -> Option<f64> {
+        match *self {
+            Value::Number(number) => Some(number),
+            _ => None,
+        }
+    }
+
+    fn is_falsey(&self) -> bool {
+        Value::is_falsey(self)
+    }
+
+    fn equals(&self, other: &Self) -> bool {
+        self == other
+    }
+}
+
+impl Layout for NanBoxed {
+    fn number(number: f64) -> Self {
+        NanBoxed::number(number)
+    }
+
+    fn boolean(boolean: bool) -> Self {
+        NanBoxed::boolean(boolean)
+    }
+
+    fn nil() -> Self {
+        NanBoxed::NIL
+    }
+
+    fn as_number(&self) -> Option<f64> {
+        NanBoxed::as_number(*self)
+    }
+
+    fn is_falsey(&self) -> bool {
+        NanBoxed::is_falsey(*self)
+    }
+
+    fn equals(&self, other: &Self) -> bool {
+        NanBoxed::equals(*self, *other)
+    }
+}
+
+fn binary<V: Layout>(stack: &mut Vec<V>, op: impl Fn(f64, f64) -> V) -> Option<()> {
+    let b = stack.pop()?.as_number()?;
+    let a = stack.pop()?.as_number()?;
+    stack.push(op(a, b));
+    Some(())
+}
+
+/// Run `iterations` times the bytecode of
+/// `if (!(i * 0.5 + 1 < limit)) count = count + 1; mixed[i] == mixed[i + 1];`
+fn run<V: Layout>(iterations: usize) -> usize {
+    let mixed = [V::number(1.0), V::nil(), V::boolean(true), V::number(1.0)];
+    let limit = iterations as f64 / 4.0;
+    let mut stack = Vec::with_capacity(256);
+    let mut count = 0;
+
+    for i in 0..iterations {
+        stack.push(V::number(i as f64));
+        stack.push(V::number(0.5));
+        binary(&mut stack, |a, b| V::number(a * b)).unwrap();
+        stack.push(V::number(1.0));
+        binary(&mut stack, |a, b| V::number(a + b)).unwrap();
+        stack.push(V::number(limit));
+        binary(&mut stack, |a, b| V::boolean(a < b)).unwrap();
+        let condition = stack.pop().unwrap();
+        stack.push(V::boolean(condition.is_falsey()));
+        if !stack.pop().unwrap().is_falsey() {
+            count += 1;
+        }
+
+        let b = mixed[(i + 1) % mixed.len()].clone();
+        let a = mixed[i % mixed.len()].clone();
+        stack.push(V::boolean(a.equals(&b)));
+        stack.pop();
+    }
+
+    count
+}
+
+fn value(c: &mut Criterion) {
+    let mut group = c.benchmark_group("value");
+
+    for iterations in [1_000, 100_000] {
+        group.throughput(Throughput::Elements(iterations as u64));
+
+        group.bench_with_input(
+            BenchmarkId::new("enum", iterations),
+            &iterations,
+            |b, &n| b.iter(|| run::<Value>(black_box(n))),
+        );
+        group.bench_with_input(
+            BenchmarkId::new("nan_boxing", iterations),
+            &iterations,
+            |b, &n| b.iter(|| run::<NanBoxed>(black_box(n))),
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, value);
+criterion_main!(benches);
@@ -0,0 +1,51 @@
+//! Values packed in the 64 bits of a double, as in the last chapter of the
+//! book. Numbers are stored as is; every other value is a quiet NaN with a tag
+//! in its low bits. A value is 8 bytes and `Copy`, where `Value` is larger
+//! and has to bump a reference count when it holds an object.
+
+const QNAN: u64 = 0x7ffc_0000_0000_0000;
+
+const TAG_NIL: u64 = 1;
+const TAG_FALSE: u64 = 2;
+const TAG_TRUE: u64 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NanBoxed(u64);
+
+impl NanBoxed {
+    pub const NIL: Self = Self(QNAN | TAG_NIL);
+    pub const FALSE: Self = Self(QNAN | TAG_FALSE);
+    pub const TRUE: Self = Self(QNAN | TAG_TRUE);
+
+    pub fn number(number: f64) -> Self {
+        Self(number.to_bits())
+    }
+
+    pub fn boolean(boolean: bool) -> Self {
+        if boolean {
+            Self::TRUE
+        } else {
+            Self::FALSE
+        }
+    }
+
+    pub fn is_number(self) -> bool {
+        self.0 & QNAN != QNAN
+    }
+
+    pub fn as_number(self) -> Option<f64> {
+        self.is_number().then(|| f64::from_bits(self.0))
+    }
+
+    pub fn is_falsey(self) -> bool {
+        self == Self::NIL || self == Self::FALSE
+    }
+
+    /// Bit equality, except for numbers so that `NaN != NaN`.
+    pub fn equals(self, other: Self) -> bool {
+        match (self.as_number(), other.as_number()) {
+            (Some(a), Some(b)) => a == b,
+            _ => self == other,
+        }
+    }
+}
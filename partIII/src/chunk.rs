@@ -1,14 +1,42 @@
-use crate::value::Value;
+use std::num::TryFromIntError;
+
+use crate::{object::ObjString, value::Value};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(u8)]
 pub enum OpCode {
     Constant,
+    Nil,
+    True,
+    False,
+    Equal,
+    Greater,
+    Less,
     Add,
     Subtract,
     Multiply,
     Divide,
+    Modulo,
+    IntegerDivide,
+    Power,
+    Not,
     Negate,
+    Print,
+    Pop,
+    DefineGlobal,
+    GetGlobal,
+    SetGlobal,
+    GetLocal,
+    SetLocal,
+    /// Jump forward by the 16-bit operand.
+    Jump,
+    /// Jump forward by the 16-bit operand if the top of the stack is falsey,
+    /// without popping it.
+    JumpIfFalse,
+    /// Jump backward by the 16-bit operand.
+    Loop,
+    /// Call the value below the arguments, the operand is their count.
+    Call,
     Return,
 }
 
@@ -17,6 +45,8 @@ pub struct Chunk {
     pub code: Vec<u8>,
     pub lines: Vec<usize>,
     pub constants: Vec<Value>,
+    /// Names of the global variables, the operand of the global instructions.
+    pub names: Vec<ObjString>,
 }
 
 impl Chunk {
@@ -33,11 +63,36 @@ impl Chunk {
         self.code[idx]
     }
 
+    /// The big-endian 16-bit operand at `idx`.
+    pub fn read_u16(&self, idx: usize) -> u16 {
+        u16::from_be_bytes([self.code[idx], self.code[idx + 1]])
+    }
+
+    /// Point the jump whose operand is at `offset` to the next instruction
+    /// to be written. Fails if it's too far to encode.
+    pub fn patch_jump(&mut self, offset: usize) -> Result<(), TryFromIntError> {
+        // the jump is relative to the end of its operand
+        let jump = u16::try_from(self.code.len() - offset - 2)?;
+        self.code[offset..offset + 2].copy_from_slice(&jump.to_be_bytes());
+        Ok(())
+    }
+
     pub fn add_constant(&mut self, value: impl Into<Value>) -> usize {
         self.constants.push(value.into());
         self.constants.len() - 1
     }
 
+    /// The index of a global variable name, added if it's not there yet.
+    pub fn add_name(&mut self, name: ObjString) -> usize {
+        match self.names.iter().position(|n| *n == name) {
+            Some(idx) => idx,
+            None => {
+                self.names.push(name);
+                self.names.len() - 1
+            }
+        }
+    }
+
     pub fn disassemble_chunk(&self, name: impl AsRef<str>) {
         println!("== {} ==", name.as_ref());
         let mut offset = 0;
@@ -54,18 +109,36 @@ impl Chunk {
             print!("{:4} ", self.lines[offset]);
         }
 
-        let instruction: u8 = self.code[offset];
-        match unsafe { std::mem::transmute(instruction) } {
-            ins @ (OpCode::Negate
+        // the compiler only writes valid opcodes at instruction boundaries
+        let instruction = unsafe { std::mem::transmute::<u8, OpCode>(self.code[offset]) };
+        match instruction {
+            ins @ (OpCode::Nil
+            | OpCode::True
+            | OpCode::False
+            | OpCode::Equal
+            | OpCode::Greater
+            | OpCode::Less
+            | OpCode::Not
+            | OpCode::Negate
             | OpCode::Add
             | OpCode::Subtract
             | OpCode::Multiply
             | OpCode::Divide
+            | OpCode::Modulo
+            | OpCode::IntegerDivide
+            | OpCode::Power
+            | OpCode::Print
+            | OpCode::Pop
             | OpCode::Return) => self.simple_instruction(format!("{:?}", ins), offset),
             ins @ OpCode::Constant => self.constant_instruction(format!("{:?}", ins), offset),
-            _ => {
-                println!("Unknown opcode {}", instruction);
-                offset + 1
+            ins @ (OpCode::DefineGlobal | OpCode::GetGlobal | OpCode::SetGlobal) => {
+                self.name_instruction(format!("{:?}", ins), offset)
+            }
+            ins @ (OpCode::GetLocal | OpCode::SetLocal | OpCode::Call) => {
+                self.byte_instruction(format!("{:?}", ins), offset)
+            }
+            ins @ (OpCode::Jump | OpCode::JumpIfFalse | OpCode::Loop) => {
+                self.jump_instruction(format!("{:?}", ins), offset)
             }
         }
     }
@@ -85,6 +158,45 @@ impl Chunk {
         );
         offset + 2
     }
+
+    pub fn name_instruction(&self, name: impl AsRef<str>, offset: usize) -> usize {
+        let idx = self.code[offset + 1];
+        println!(
+            "{:16} {:4} `{}`",
+            name.as_ref(),
+            idx,
+            self.names[idx as usize]
+        );
+        offset + 2
+    }
+
+    pub fn byte_instruction(&self, name: impl AsRef<str>, offset: usize) -> usize {
+        let slot = self.code[offset + 1];
+        println!("{:16} {:4}", name.as_ref(), slot);
+        offset + 2
+    }
+
+    pub fn jump_instruction(&self, name: impl AsRef<str>, offset: usize) -> usize {
+        println!(
+            "{:16} {:4} -> {:04}",
+            name.as_ref(),
+            offset,
+            self.jump_target(offset)
+        );
+        offset + 3
+    }
+
+    /// Where the jump instruction at `offset` lands, backward for a `Loop`.
+    pub fn jump_target(&self, offset: usize) -> usize {
+        let jump = self.read_u16(offset + 1) as usize;
+        // the jump is relative to the end of its operand
+        let next = offset + 3;
+        if self.code[offset] == OpCode::Loop as u8 {
+            next - jump
+        } else {
+            next + jump
+        }
+    }
 }
 
 impl From<OpCode> for u8 {
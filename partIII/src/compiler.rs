@@ -1,8 +1,9 @@
-use std::ops::Add;
+use std::{ops::Add, rc::Rc};
 
 use crate::{
     chunk::{Chunk, OpCode},
     error::ParserError,
+    object::{Heap, ObjFunction, ObjString},
     scanner::{Scanner, Token, TokenType},
     value::Value,
 };
@@ -14,7 +15,56 @@ pub struct Parser<'a> {
     scanner: Scanner<'a>,
     current: Token<'a>,
     previous: Token<'a>,
-    chunk: Chunk,
+    /// Where string constants and variable names are interned.
+    heap: &'a mut Heap,
+    /// The function being compiled, the ones enclosing it are set aside
+    /// until it ends.
+    compiler: Compiler<'a>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FunctionKind {
+    Function,
+    Script,
+}
+
+/// What's being compiled of a function.
+#[derive(Debug)]
+struct Compiler<'a> {
+    function: ObjFunction,
+    kind: FunctionKind,
+    /// The local variables in scope, in the order of their stack slots.
+    locals: Vec<Local<'a>>,
+    scope_depth: usize,
+}
+
+impl<'a> Compiler<'a> {
+    fn new(kind: FunctionKind, name: Option<ObjString>) -> Self {
+        Self {
+            function: ObjFunction {
+                name,
+                ..Default::default()
+            },
+            kind,
+            // the first slot holds the function being called
+            locals: vec![Local {
+                name: Token {
+                    ty: TokenType::Identifier,
+                    lexeme: "",
+                    line: 0,
+                },
+                depth: Some(0),
+            }],
+            scope_depth: 0,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Local<'a> {
+    name: Token<'a>,
+    /// The depth of the scope declaring it, `None` until it's initialized.
+    depth: Option<usize>,
 }
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd)]
@@ -27,8 +77,9 @@ pub enum Precedence {
     Equality,   // == !=
     Comparison, // < > <= >=
     Term,       // + -
-    Factor,     // * /
-    Unary,      // ! -
+    Factor,     // * / % ~/
+    Unary,      // ! -
+    Exponent,   // **
     Call,       // . ()
     Primary,
 }
@@ -46,7 +97,9 @@ impl Add<u8> for Precedence {
     }
 }
 
-type ParseFn<'a> = fn(&mut Parser<'a>) -> Result<()>;
+/// Parse functions are told whether the expression can be an assignment
+/// target, so `a * b = c` isn't compiled as `a * (b = c)`.
+type ParseFn<'a> = fn(&mut Parser<'a>, bool) -> Result<()>;
 
 pub struct ParseRule<'a> {
     pub prefix: Option<ParseFn<'a>>,
@@ -91,35 +144,53 @@ impl<'a> ParseRule<'a> {
         use TokenType::*;
 
         match ty {
-            RightParen | LeftBrace | RightBrace | Comma | Semicolon | Dot | Bang | BangEqual
-            | Equal | EqualEqual | Greater | GreaterEqual | Less | LessEqual | Identifier | And
-            | Class | Else | False | Fun | For | If | Nil | Or | Print | Return | Super | This
-            | True | Var | While | EoF | Error | String => Self::prec(Precedence::None),
-            LeftParen => Self::prefix(Parser::grouping, Precedence::None),
+            RightParen | LeftBrace | RightBrace | Comma | Semicolon | Dot | Equal | Class
+            | Else | Fun | For | If | Print | Return | Super | This | Var | While | EoF | Error => {
+                Self::prec(Precedence::None)
+            }
+            And => Self::infix(Parser::and, Precedence::And),
+            Or => Self::infix(Parser::or, Precedence::Or),
+            False | Nil | True => Self::prefix(Parser::literal, Precedence::None),
+            Bang => Self::prefix(Parser::unary, Precedence::None),
+            BangEqual | EqualEqual => Self::infix(Parser::binary, Precedence::Equality),
+            Greater | GreaterEqual | Less | LessEqual => {
+                Self::infix(Parser::binary, Precedence::Comparison)
+            }
+            Identifier => Self::prefix(Parser::variable, Precedence::None),
+            LeftParen => Self::full(Parser::grouping, Parser::call, Precedence::Call),
             Minus => Self::full(Parser::unary, Parser::binary, Precedence::Term),
             Plus => Self::infix(Parser::binary, Precedence::Term),
-            Slash | Star => Self::infix(Parser::binary, Precedence::Factor),
+            Slash | Star | Percent | TildeSlash => Self::infix(Parser::binary, Precedence::Factor),
+            StarStar => Self::infix(Parser::binary, Precedence::Exponent),
             Number => Self::prefix(Parser::number, Precedence::None),
+            String => Self::prefix(Parser::string, Precedence::None),
         }
     }
 }
 
 impl<'a> Parser<'a> {
-    pub fn compile(source: &'a str) -> Result<Chunk> {
-        let mut scanner = Scanner::new(source);
-        let tok = scanner.scan_token();
+    /// Compile a script into the function running its top-level code.
+    pub fn compile(source: &'a str, heap: &'a mut Heap) -> Result<ObjFunction> {
+        // placeholder until the first `advance` reads the first token
+        let start = Token {
+            ty: TokenType::EoF,
+            lexeme: "",
+            line: 1,
+        };
 
         let mut parser = Self {
-            scanner,
-            current: tok.clone(),
-            previous: tok,
-            chunk: Chunk::new(),
+            scanner: Scanner::new(source),
+            current: start.clone(),
+            previous: start,
+            heap,
+            compiler: Compiler::new(FunctionKind::Script, None),
         };
 
         parser.advance()?;
-        parser.expression()?;
-        parser.consume(TokenType::EoF, "Expect end of expression.")?;
-        parser.end_compiler();
+        while !parser.follow(TokenType::EoF)? {
+            parser.declaration()?;
+        }
+        let function = parser.end_compiler();
         /*
         let mut line = usize::MAX;
         loop {
@@ -138,7 +209,7 @@ impl<'a> Parser<'a> {
         }
         */
 
-        Ok(parser.chunk)
+        Ok(function)
     }
 
     fn advance(&mut self) -> Result<()> {
@@ -161,8 +232,13 @@ impl<'a> Parser<'a> {
         Ok(())
     }
 
+    fn chunk(&mut self) -> &mut Chunk {
+        &mut self.compiler.function.chunk
+    }
+
     fn emit_byte(&mut self, byte: impl Into<u8>) {
-        self.chunk.write(byte, self.previous.line)
+        let line = self.previous.line;
+        self.chunk().write(byte, line)
     }
 
     fn emit_bytes(&mut self, byte1: impl Into<u8>, byte2: impl Into<u8>) {
@@ -170,13 +246,42 @@ impl<'a> Parser<'a> {
         self.emit_byte(byte2);
     }
 
+    /// Emit a jump with a placeholder operand, and return the offset of the
+    /// operand for `patch_jump`.
+    fn emit_jump(&mut self, instruction: OpCode) -> usize {
+        self.emit_byte(instruction);
+        self.emit_bytes(0xff, 0xff);
+        self.chunk().code.len() - 2
+    }
+
+    fn patch_jump(&mut self, offset: usize) -> Result<()> {
+        if self.chunk().patch_jump(offset).is_err() {
+            self.error_at(&self.previous, "Too much code to jump over.")?;
+        }
+        Ok(())
+    }
+
+    /// Emit a jump back to `loop_start`.
+    fn emit_loop(&mut self, loop_start: usize) -> Result<()> {
+        self.emit_byte(OpCode::Loop);
+
+        // the jump is relative to the end of the operand
+        let Ok(jump) = u16::try_from(self.chunk().code.len() - loop_start + 2) else {
+            return self.error_at(&self.previous, "Loop body too large.");
+        };
+        let [high, low] = jump.to_be_bytes();
+        self.emit_bytes(high, low);
+        Ok(())
+    }
+
+    /// Return `nil` when the end of the function body is reached.
     fn emit_return(&mut self) {
-        self.emit_byte(OpCode::Return);
+        self.emit_bytes(OpCode::Nil, OpCode::Return);
     }
 
     fn make_constant(&mut self, value: impl Into<Value>) -> Result<u8> {
         let value = value.into();
-        let constant = self.chunk.add_constant(value);
+        let constant = self.chunk().add_constant(value);
         if constant > u8::MAX as usize {
             self.error_at_current("Too many constant in one chunk")?;
         }
@@ -190,12 +295,417 @@ impl<'a> Parser<'a> {
         Ok(())
     }
 
-    fn end_compiler(&mut self) {
+    fn end_compiler(&mut self) -> ObjFunction {
         log::trace!("end compiler");
         self.emit_return();
+        let function = std::mem::take(&mut self.compiler.function);
+        if log::log_enabled!(log::Level::Debug) {
+            function.chunk.disassemble_chunk(function.to_string());
+        }
+        function
+    }
+
+    fn begin_scope(&mut self) {
+        self.compiler.scope_depth += 1;
+    }
+
+    fn end_scope(&mut self) {
+        self.compiler.scope_depth -= 1;
+
+        // the locals of the scope are on top of the stack
+        while self
+            .compiler
+            .locals
+            .last()
+            .is_some_and(|local| local.depth > Some(self.compiler.scope_depth))
+        {
+            self.emit_byte(OpCode::Pop);
+            self.compiler.locals.pop();
+        }
+    }
+
+    fn declaration(&mut self) -> Result<()> {
+        log::trace!("parsing declaration");
+        if self.follow(TokenType::Fun)? {
+            self.fun_declaration()
+        } else if self.follow(TokenType::Var)? {
+            self.var_declaration()
+        } else {
+            self.statement()
+        }
+    }
+
+    fn fun_declaration(&mut self) -> Result<()> {
+        log::trace!("parsing fun declaration");
+        let global = self.parse_variable("Expect function name.")?;
+        // the name is usable as soon as the body starts, but until closures
+        // capture enclosing locals, the body of a local function can't see
+        // it and looks up a global instead
+        self.mark_initialized();
+        self.function(FunctionKind::Function)?;
+        self.define_variable(global);
+        Ok(())
+    }
+
+    /// Compile the parameters and body of a function, and load it.
+    fn function(&mut self, kind: FunctionKind) -> Result<()> {
+        let name = self.heap.intern(self.previous.lexeme);
+        let enclosing = std::mem::replace(&mut self.compiler, Compiler::new(kind, Some(name)));
+        // the parameters are locals of the body, there is no end_scope since
+        // the whole frame is discarded on return
+        self.begin_scope();
+
+        self.consume(TokenType::LeftParen, "Expect `(` after function name.")?;
+        if !self.check(TokenType::RightParen) {
+            loop {
+                self.compiler.function.arity += 1;
+                if self.compiler.function.arity > u8::MAX as usize {
+                    self.error_at_current("Can't have more than 255 parameters.")?;
+                }
+                let constant = self.parse_variable("Expect parameter name.")?;
+                self.define_variable(constant);
+
+                if !self.follow(TokenType::Comma)? {
+                    break;
+                }
+            }
+        }
+        self.consume(TokenType::RightParen, "Expect `)` after parameters.")?;
+        self.consume(TokenType::LeftBrace, "Expect `{` before function body.")?;
+        self.block()?;
+
+        let function = self.end_compiler();
+        self.compiler = enclosing;
+        self.emit_constant(Rc::new(function))
+    }
+
+    fn var_declaration(&mut self) -> Result<()> {
+        log::trace!("parsing var declaration");
+        let global = self.parse_variable("Expect variable name.")?;
+
+        if self.follow(TokenType::Equal)? {
+            self.expression()?;
+        } else {
+            self.emit_byte(OpCode::Nil);
+        }
+        self.consume(
+            TokenType::Semicolon,
+            "Expect `;` after variable declaration.",
+        )?;
+
+        self.define_variable(global);
+        Ok(())
+    }
+
+    fn statement(&mut self) -> Result<()> {
+        log::trace!("parsing statement");
+        if self.follow(TokenType::Print)? {
+            self.print_statement()
+        } else if self.follow(TokenType::If)? {
+            self.if_statement()
+        } else if self.follow(TokenType::Return)? {
+            self.return_statement()
+        } else if self.follow(TokenType::While)? {
+            self.while_statement()
+        } else if self.follow(TokenType::For)? {
+            self.for_statement()
+        } else if self.follow(TokenType::LeftBrace)? {
+            self.begin_scope();
+            self.block()?;
+            self.end_scope();
+            Ok(())
+        } else {
+            self.expression_statement()
+        }
+    }
+
+    fn print_statement(&mut self) -> Result<()> {
+        self.expression()?;
+        self.consume(TokenType::Semicolon, "Expect `;` after value.")?;
+        self.emit_byte(OpCode::Print);
+        Ok(())
+    }
+
+    fn return_statement(&mut self) -> Result<()> {
+        if self.compiler.kind == FunctionKind::Script {
+            self.error_at(&self.previous, "Can't return from top-level code.")?;
+        }
+
+        if self.follow(TokenType::Semicolon)? {
+            self.emit_return();
+        } else {
+            self.expression()?;
+            self.consume(TokenType::Semicolon, "Expect `;` after return value.")?;
+            self.emit_byte(OpCode::Return);
+        }
+        Ok(())
+    }
+
+    fn if_statement(&mut self) -> Result<()> {
+        self.consume(TokenType::LeftParen, "Expect `(` after `if`.")?;
+        self.expression()?;
+        self.consume(TokenType::RightParen, "Expect `)` after condition.")?;
+
+        // the condition is popped on both branches
+        let then_jump = self.emit_jump(OpCode::JumpIfFalse);
+        self.emit_byte(OpCode::Pop);
+        self.statement()?;
+
+        let else_jump = self.emit_jump(OpCode::Jump);
+        self.patch_jump(then_jump)?;
+        self.emit_byte(OpCode::Pop);
+
+        if self.follow(TokenType::Else)? {
+            self.statement()?;
+        }
+        self.patch_jump(else_jump)
+    }
+
+    fn while_statement(&mut self) -> Result<()> {
+        let loop_start = self.chunk().code.len();
+        self.consume(TokenType::LeftParen, "Expect `(` after `while`.")?;
+        self.expression()?;
+        self.consume(TokenType::RightParen, "Expect `)` after condition.")?;
+
+        let exit_jump = self.emit_jump(OpCode::JumpIfFalse);
+        self.emit_byte(OpCode::Pop);
+        self.statement()?;
+        self.emit_loop(loop_start)?;
+
+        self.patch_jump(exit_jump)?;
+        self.emit_byte(OpCode::Pop);
+        Ok(())
+    }
+
+    fn for_statement(&mut self) -> Result<()> {
+        // the initializer's variable is scoped to the loop
+        self.begin_scope();
+        self.consume(TokenType::LeftParen, "Expect `(` after `for`.")?;
+        if self.follow(TokenType::Semicolon)? {
+            // no initializer
+        } else if self.follow(TokenType::Var)? {
+            self.var_declaration()?;
+        } else {
+            self.expression_statement()?;
+        }
+
+        let mut loop_start = self.chunk().code.len();
+        let mut exit_jump = None;
+        if !self.follow(TokenType::Semicolon)? {
+            self.expression()?;
+            self.consume(TokenType::Semicolon, "Expect `;` after loop condition.")?;
+
+            exit_jump = Some(self.emit_jump(OpCode::JumpIfFalse));
+            self.emit_byte(OpCode::Pop);
+        }
+
+        // the increment is compiled before the body but runs after it
+        if !self.follow(TokenType::RightParen)? {
+            let body_jump = self.emit_jump(OpCode::Jump);
+            let increment_start = self.chunk().code.len();
+            self.expression()?;
+            self.emit_byte(OpCode::Pop);
+            self.consume(TokenType::RightParen, "Expect `)` after for clauses.")?;
+
+            self.emit_loop(loop_start)?;
+            loop_start = increment_start;
+            self.patch_jump(body_jump)?;
+        }
+
+        self.statement()?;
+        self.emit_loop(loop_start)?;
+
+        if let Some(exit_jump) = exit_jump {
+            self.patch_jump(exit_jump)?;
+            self.emit_byte(OpCode::Pop);
+        }
+        self.end_scope();
+        Ok(())
+    }
+
+    fn expression_statement(&mut self) -> Result<()> {
+        self.expression()?;
+        self.consume(TokenType::Semicolon, "Expect `;` after expression.")?;
+        self.emit_byte(OpCode::Pop);
+        Ok(())
+    }
+
+    fn block(&mut self) -> Result<()> {
+        while !self.check(TokenType::RightBrace) && !self.check(TokenType::EoF) {
+            self.declaration()?;
+        }
+
+        self.consume(TokenType::RightBrace, "Expect `}` after block.")
+    }
+
+    /// Consume a variable name and declare it. Returns the index of its name
+    /// for a global, locals aren't looked up by name at runtime.
+    fn parse_variable(&mut self, message: &str) -> Result<u8> {
+        self.consume(TokenType::Identifier, message)?;
+
+        self.declare_variable()?;
+        if self.compiler.scope_depth > 0 {
+            return Ok(0);
+        }
+
+        let name = self.previous.clone();
+        self.identifier_constant(&name)
+    }
+
+    fn identifier_constant(&mut self, name: &Token) -> Result<u8> {
+        let string = self.heap.intern(name.lexeme);
+        let idx = self.chunk().add_name(string);
+        if idx > u8::MAX as usize {
+            self.error_at(name, "Too many global variables in one chunk.")?;
+        }
+
+        Ok(idx as u8)
+    }
+
+    fn declare_variable(&mut self) -> Result<()> {
+        if self.compiler.scope_depth == 0 {
+            return Ok(());
+        }
+
+        let name = self.previous.clone();
+        let redeclared = self
+            .compiler
+            .locals
+            .iter()
+            .rev()
+            .take_while(|local| {
+                local
+                    .depth
+                    .is_none_or(|depth| depth >= self.compiler.scope_depth)
+            })
+            .any(|local| local.name.lexeme == name.lexeme);
+        if redeclared {
+            self.error_at(&name, "Already a variable with this name in this scope.")?;
+        }
+
+        self.add_local(name)
+    }
+
+    fn add_local(&mut self, name: Token<'a>) -> Result<()> {
+        if self.compiler.locals.len() > u8::MAX as usize {
+            return self.error_at(&name, "Too many local variables in function.");
+        }
+
+        self.compiler.locals.push(Local { name, depth: None });
+        Ok(())
+    }
+
+    /// Let the last local declared be read, globals are late bound.
+    fn mark_initialized(&mut self) {
+        if self.compiler.scope_depth == 0 {
+            return;
+        }
+        if let Some(local) = self.compiler.locals.last_mut() {
+            local.depth = Some(self.compiler.scope_depth);
+        }
+    }
+
+    fn define_variable(&mut self, global: u8) {
+        if self.compiler.scope_depth > 0 {
+            // the value is already in the local's slot
+            self.mark_initialized();
+            return;
+        }
+
+        self.emit_bytes(OpCode::DefineGlobal, global);
+    }
+
+    /// The stack slot of a local variable, `None` for a global.
+    fn resolve_local(&self, name: &Token) -> Result<Option<u8>> {
+        let Some(slot) = self
+            .compiler
+            .locals
+            .iter()
+            .rposition(|local| local.name.lexeme == name.lexeme)
+        else {
+            return Ok(None);
+        };
+
+        if self.compiler.locals[slot].depth.is_none() {
+            self.error_at(name, "Can't read local variable in its own initializer.")?;
+        }
+        Ok(Some(slot as u8))
+    }
+
+    fn variable(&mut self, can_assign: bool) -> Result<()> {
+        log::trace!("parsing variable");
+        let name = self.previous.clone();
+
+        let (get, set, arg) = match self.resolve_local(&name)? {
+            Some(slot) => (OpCode::GetLocal, OpCode::SetLocal, slot),
+            None => {
+                let idx = self.identifier_constant(&name)?;
+                (OpCode::GetGlobal, OpCode::SetGlobal, idx)
+            }
+        };
+
+        if can_assign && self.follow(TokenType::Equal)? {
+            self.expression()?;
+            self.emit_bytes(set, arg);
+        } else {
+            self.emit_bytes(get, arg);
+        }
+
+        Ok(())
+    }
+
+    /// The left operand is on the stack, it's the result if it's falsey.
+    fn and(&mut self, _can_assign: bool) -> Result<()> {
+        log::trace!("parsing and");
+        let end_jump = self.emit_jump(OpCode::JumpIfFalse);
+
+        self.emit_byte(OpCode::Pop);
+        self.parse_precedence(Precedence::And)?;
+
+        self.patch_jump(end_jump)
+    }
+
+    /// The left operand is on the stack, it's the result if it's truthy.
+    fn or(&mut self, _can_assign: bool) -> Result<()> {
+        log::trace!("parsing or");
+        let else_jump = self.emit_jump(OpCode::JumpIfFalse);
+        let end_jump = self.emit_jump(OpCode::Jump);
+
+        self.patch_jump(else_jump)?;
+        self.emit_byte(OpCode::Pop);
+        self.parse_precedence(Precedence::Or)?;
+
+        self.patch_jump(end_jump)
+    }
+
+    fn call(&mut self, _can_assign: bool) -> Result<()> {
+        log::trace!("parsing call");
+        let arg_count = self.argument_list()?;
+        self.emit_bytes(OpCode::Call, arg_count);
+        Ok(())
     }
 
-    fn grouping(&mut self) -> Result<()> {
+    /// Compile the arguments of a call, and return how many there are.
+    fn argument_list(&mut self) -> Result<u8> {
+        let mut arg_count: usize = 0;
+        if !self.check(TokenType::RightParen) {
+            loop {
+                self.expression()?;
+                if arg_count == u8::MAX as usize {
+                    self.error_at(&self.previous, "Can't have more than 255 arguments.")?;
+                }
+                arg_count += 1;
+
+                if !self.follow(TokenType::Comma)? {
+                    break;
+                }
+            }
+        }
+        self.consume(TokenType::RightParen, "Expect `)` after arguments.")?;
+        Ok(arg_count as u8)
+    }
+
+    fn grouping(&mut self, _can_assign: bool) -> Result<()> {
         log::trace!("parsing grouping");
         self.expression()?;
         self.consume(TokenType::RightParen, "Expect `)` after expression.")
@@ -206,13 +716,32 @@ impl<'a> Parser<'a> {
         self.parse_precedence(Precedence::Assignment)
     }
 
-    fn number(&mut self) -> Result<()> {
+    fn number(&mut self, _can_assign: bool) -> Result<()> {
         log::trace!("parsing number");
         let value: f64 = self.previous.lexeme.parse().unwrap();
         self.emit_constant(value)
     }
 
-    fn unary(&mut self) -> Result<()> {
+    fn string(&mut self, _can_assign: bool) -> Result<()> {
+        log::trace!("parsing string");
+        // strip the quotes
+        let lexeme = self.previous.lexeme;
+        let string = self.heap.intern(&lexeme[1..lexeme.len() - 1]);
+        self.emit_constant(string)
+    }
+
+    fn literal(&mut self, _can_assign: bool) -> Result<()> {
+        log::trace!("parsing literal");
+        match self.previous.ty {
+            TokenType::False => self.emit_byte(OpCode::False),
+            TokenType::Nil => self.emit_byte(OpCode::Nil),
+            TokenType::True => self.emit_byte(OpCode::True),
+            _ => unreachable!(),
+        }
+        Ok(())
+    }
+
+    fn unary(&mut self, _can_assign: bool) -> Result<()> {
         log::trace!("parsing unary");
         let operator_type = self.previous.ty;
 
@@ -221,6 +750,7 @@ impl<'a> Parser<'a> {
 
         // emit the operator instruction
         match operator_type {
+            TokenType::Bang => self.emit_byte(OpCode::Not),
             TokenType::Minus => self.emit_byte(OpCode::Negate),
             _ => unreachable!(),
         }
@@ -228,18 +758,33 @@ impl<'a> Parser<'a> {
         Ok(())
     }
 
-    fn binary(&mut self) -> Result<()> {
+    fn binary(&mut self, _can_assign: bool) -> Result<()> {
         log::trace!("parsing binary");
         let operator_type = self.previous.ty;
 
         let rule = ParseRule::get_rule(operator_type);
 
-        self.parse_precedence(rule.precedence + 1)?;
+        // `**` is right-associative, its right operand may be another `**`
+        if operator_type == TokenType::StarStar {
+            self.parse_precedence(rule.precedence)?;
+        } else {
+            self.parse_precedence(rule.precedence + 1)?;
+        }
         match operator_type {
+            TokenType::BangEqual => self.emit_bytes(OpCode::Equal, OpCode::Not),
+            TokenType::EqualEqual => self.emit_byte(OpCode::Equal),
+            TokenType::Greater => self.emit_byte(OpCode::Greater),
+            // `a >= b` is `!(a < b)`, which differs for NaN like in clox
+            TokenType::GreaterEqual => self.emit_bytes(OpCode::Less, OpCode::Not),
+            TokenType::Less => self.emit_byte(OpCode::Less),
+            TokenType::LessEqual => self.emit_bytes(OpCode::Greater, OpCode::Not),
             TokenType::Plus => self.emit_byte(OpCode::Add),
             TokenType::Minus => self.emit_byte(OpCode::Subtract),
             TokenType::Star => self.emit_byte(OpCode::Multiply),
             TokenType::Slash => self.emit_byte(OpCode::Divide),
+            TokenType::Percent => self.emit_byte(OpCode::Modulo),
+            TokenType::TildeSlash => self.emit_byte(OpCode::IntegerDivide),
+            TokenType::StarStar => self.emit_byte(OpCode::Power),
             _ => unreachable!(),
         }
 
@@ -251,26 +796,43 @@ impl<'a> Parser<'a> {
 
         self.advance()?;
 
-        println!("{:?}", self);
-
+        let can_assign = precedence <= Precedence::Assignment;
         let parse_rule = ParseRule::get_rule(self.previous.ty);
         if let Some(prefix_rule) = parse_rule.prefix {
-            prefix_rule(self)?;
+            prefix_rule(self, can_assign)?;
         } else {
             self.error_at_current("Expect expression.")?;
         }
 
         while precedence <= ParseRule::get_rule(self.current.ty).precedence {
             self.advance()?;
-            if let Some(infix_rule) = ParseRule::get_rule(self.current.ty).infix {
-                infix_rule(self)?;
+            if let Some(infix_rule) = ParseRule::get_rule(self.previous.ty).infix {
+                infix_rule(self, can_assign)?;
             } else {
                 self.error_at_current("Unreachable.")?;
             }
         }
+
+        // nothing above took the `=`, what's on its left can't be assigned
+        if can_assign && self.check(TokenType::Equal) {
+            self.error_at_current("Invalid assignment target.")?;
+        }
         Ok(())
     }
 
+    fn check(&self, ty: TokenType) -> bool {
+        self.current.ty == ty
+    }
+
+    /// Consume the current token if it's of type `ty`.
+    fn follow(&mut self, ty: TokenType) -> Result<bool> {
+        if !self.check(ty) {
+            return Ok(false);
+        }
+        self.advance()?;
+        Ok(true)
+    }
+
     fn consume(&mut self, ty: TokenType, message: impl AsRef<str>) -> Result<()> {
         if self.current.ty == ty {
             return self.advance();
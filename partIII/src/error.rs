@@ -27,7 +27,7 @@ pub enum SetupError {
 
 #[derive(Error, Debug)]
 pub enum ParserError {
-    #[error("[line {line}] Error at `{token}`: {message}.")]
+    #[error("[line {line}] Error at `{token}`: {message}")]
     At {
         line: usize,
         token: String,
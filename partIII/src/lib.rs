@@ -0,0 +1,8 @@
+pub mod chunk;
+pub mod compiler;
+pub mod error;
+pub mod native_functions;
+pub mod object;
+pub mod scanner;
+pub mod value;
+pub mod vm;
@@ -1,24 +1,16 @@
-mod chunk;
-mod compiler;
-mod error;
-mod scanner;
-mod value;
-mod vm;
-
 use std::{
     io::{BufRead, Write},
     path::Path,
 };
 
-use chunk::*;
-use error::*;
-use vm::*;
+use clox::error::*;
+use clox::vm::*;
 
 fn main() -> Result<()> {
     let args: Vec<_> = std::env::args().collect();
 
     if args.len() > 3 {
-        return Err(SetupError::Usage)?;
+        Err(SetupError::Usage)?;
     }
 
     pretty_env_logger::init();
@@ -36,9 +28,11 @@ fn run_file(filename: impl AsRef<Path>) -> Result<()> {
     let file = std::fs::read_to_string(filename).map_err(SetupError::from)?;
     let mut vm = Vm::new();
 
-    run(file, &mut vm)?;
-
-    Ok(())
+    match run(file, &mut vm)? {
+        InterpretResult::Ok => Ok(()),
+        InterpretResult::CompileError => std::process::exit(65),
+        InterpretResult::RuntimeError => std::process::exit(70),
+    }
 }
 
 fn run_prompt() -> Result<()> {
@@ -64,10 +58,9 @@ fn run_prompt() -> Result<()> {
     Ok(())
 }
 
-fn run(input: String, vm: &mut Vm) -> Result<()> {
-    println!("interpreting {input}");
-    vm.interpret(&input);
-    Ok(())
+fn run(input: String, vm: &mut Vm) -> Result<InterpretResult> {
+    log::debug!("interpreting {input}");
+    Ok(vm.interpret(&input))
     // compiler::Parser::compile(&input)?;
     // todo!()
 
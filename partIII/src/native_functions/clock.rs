@@ -0,0 +1,20 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{
+    object::{Heap, ObjNative},
+    value::Value,
+};
+
+pub const CLOCK: ObjNative = ObjNative {
+    name: "clock",
+    arity: 0,
+    function: clock,
+};
+
+fn clock(_heap: &mut Heap, _arguments: &[Value]) -> Result<Value, String> {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("Time went backwards");
+
+    Ok(timestamp.as_secs_f64().into())
+}
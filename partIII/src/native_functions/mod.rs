@@ -0,0 +1,12 @@
+mod clock;
+mod parse_int;
+mod read_lines;
+
+pub use clock::*;
+pub use parse_int::*;
+pub use read_lines::*;
+
+use crate::object::ObjNative;
+
+/// The natives defined as globals by a new VM.
+pub const NATIVES: [ObjNative; 3] = [CLOCK, READ_LINES, PARSE_INT];
@@ -0,0 +1,22 @@
+use crate::{
+    object::{Heap, Obj, ObjNative},
+    value::Value,
+};
+
+pub const PARSE_INT: ObjNative = ObjNative {
+    name: "parseInt",
+    arity: 1,
+    function: parse_int,
+};
+
+fn parse_int(_heap: &mut Heap, arguments: &[Value]) -> Result<Value, String> {
+    let Value::Obj(Obj::String(string)) = &arguments[0] else {
+        return Err("`parseInt` expect one string argument.".to_string());
+    };
+
+    let int = string
+        .parse::<f64>()
+        .map_err(|e| format!("`parseInt` was not able to parse {string}. {e}"))?;
+
+    Ok(int.into())
+}
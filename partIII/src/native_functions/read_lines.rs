@@ -0,0 +1,25 @@
+use crate::{
+    object::{Heap, ObjNative},
+    value::Value,
+};
+
+pub const READ_LINES: ObjNative = ObjNative {
+    name: "readLines",
+    arity: 0,
+    function: read_lines,
+};
+
+/// The next line of the standard input, `nil` once it's empty.
+fn read_lines(heap: &mut Heap, _arguments: &[Value]) -> Result<Value, String> {
+    let mut line = String::new();
+    std::io::stdin()
+        .read_line(&mut line)
+        .map_err(|e| format!("`readLines` was not able to read the input. {e}"))?;
+    let line = line.strip_suffix('\n').unwrap_or(&line);
+
+    if line.is_empty() {
+        Ok(Value::Nil)
+    } else {
+        Ok(heap.intern(line).into())
+    }
+}
@@ -0,0 +1,125 @@
+use std::{
+    collections::HashSet,
+    fmt::Display,
+    hash::{Hash, Hasher},
+    ops::Deref,
+    rc::Rc,
+};
+
+use crate::{chunk::Chunk, value::Value};
+
+/// A value living on the heap, values only hold a reference to it.
+#[derive(Debug, Clone)]
+pub enum Obj {
+    String(ObjString),
+    Function(Rc<ObjFunction>),
+    Native(Rc<ObjNative>),
+}
+
+impl PartialEq for Obj {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::String(a), Self::String(b)) => a == b,
+            (Self::Function(a), Self::Function(b)) => Rc::ptr_eq(a, b),
+            (Self::Native(a), Self::Native(b)) => Rc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+}
+
+impl Display for Obj {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::String(string) => write!(f, "{string}"),
+            Self::Function(function) => write!(f, "{function}"),
+            Self::Native(native) => write!(f, "<native fn {}>", native.name),
+        }
+    }
+}
+
+/// A compiled function, the top-level code of a script is one too.
+#[derive(Debug, Default)]
+pub struct ObjFunction {
+    pub arity: usize,
+    pub chunk: Chunk,
+    /// `None` for the script.
+    pub name: Option<ObjString>,
+}
+
+impl Display for ObjFunction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.name {
+            Some(name) => write!(f, "<fn {name}>"),
+            None => write!(f, "<script>"),
+        }
+    }
+}
+
+/// Natives are called with their arguments once the VM checked their count,
+/// an error is reported as a runtime error.
+pub type NativeFn = fn(&mut Heap, &[Value]) -> Result<Value, String>;
+
+/// A function implemented in Rust.
+#[derive(Debug, Clone, Copy)]
+pub struct ObjNative {
+    pub name: &'static str,
+    pub arity: usize,
+    pub function: NativeFn,
+}
+
+/// An interned string: two strings with the same content are the same
+/// allocation, so they are compared and hashed by address.
+#[derive(Debug, Clone)]
+pub struct ObjString(Rc<str>);
+
+impl PartialEq for ObjString {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for ObjString {}
+
+impl Hash for ObjString {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Rc::as_ptr(&self.0).cast::<u8>().hash(state)
+    }
+}
+
+impl Deref for ObjString {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Display for ObjString {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Allocates the objects of the compiler and the VM. Objects are reference
+/// counted, the heap only keeps the interning table.
+#[derive(Debug, Clone, Default)]
+pub struct Heap {
+    strings: HashSet<Rc<str>>,
+}
+
+impl Heap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The string with this content, allocated if it's not there yet.
+    pub fn intern(&mut self, string: &str) -> ObjString {
+        if let Some(interned) = self.strings.get(string) {
+            return ObjString(Rc::clone(interned));
+        }
+
+        let interned: Rc<str> = Rc::from(string);
+        self.strings.insert(Rc::clone(&interned));
+        ObjString(interned)
+    }
+}
@@ -39,6 +39,9 @@ impl<'a> Scanner<'a> {
             '-' => self.make_token(TokenType::Minus),
             '+' => self.make_token(TokenType::Plus),
             '/' => self.make_token(TokenType::Slash),
+            '~' if self.follow('/') => self.make_token(TokenType::TildeSlash),
+            '%' => self.make_token(TokenType::Percent),
+            '*' if self.follow('*') => self.make_token(TokenType::StarStar),
             '*' => self.make_token(TokenType::Star),
             '!' if self.follow('=') => self.make_token(TokenType::BangEqual),
             '!' => self.make_token(TokenType::Bang),
@@ -49,17 +52,15 @@ impl<'a> Scanner<'a> {
             '>' if self.follow('=') => self.make_token(TokenType::GreaterEqual),
             '>' => self.make_token(TokenType::Greater),
             '"' => self.string(),
-            c if c.is_digit(10) => self.number(),
+            c if c.is_ascii_digit() => self.number(),
             c if c.is_ascii_alphabetic() || c == '_' => self.identifier(),
-            c => todo!("{:?}", c),
-        };
-
-        self.error_token("Unexpected character.")
+            _ => self.error_token("Unexpected character."),
+        }
     }
 
-    fn identifier(&mut self) -> Token {
+    fn identifier(&mut self) -> Token<'a> {
         let mut peek = self.peek();
-        while peek.is_ascii_alphabetic() || peek == '_' || peek.is_digit(10) {
+        while peek.is_ascii_alphabetic() || peek == '_' || peek.is_ascii_digit() {
             self.advance();
             peek = self.peek();
         }
@@ -67,7 +68,7 @@ impl<'a> Scanner<'a> {
     }
 
     fn identifier_type(&self) -> TokenType {
-        match &self.source[self.start..=self.current] {
+        match &self.source[self.start..self.current] {
             "and" => TokenType::And,
             "class" => TokenType::Class,
             "else" => TokenType::Else,
@@ -88,14 +89,14 @@ impl<'a> Scanner<'a> {
         }
     }
 
-    fn number(&mut self) -> Token {
-        while self.peek().is_digit(10) {
+    fn number(&mut self) -> Token<'a> {
+        while self.peek().is_ascii_digit() {
             self.advance();
         }
 
-        if self.peek() == '.' && self.peek_next().is_digit(10) {
+        if self.peek() == '.' && self.peek_next().is_ascii_digit() {
             self.advance();
-            while self.peek().is_digit(10) {
+            while self.peek().is_ascii_digit() {
                 self.advance();
             }
         }
@@ -103,7 +104,7 @@ impl<'a> Scanner<'a> {
         self.make_token(TokenType::Number)
     }
 
-    fn string(&mut self) -> Token {
+    fn string(&mut self) -> Token<'a> {
         while self.peek() != '"' && !self.is_at_end() {
             if self.peek() == '\n' {
                 self.line += 1;
@@ -137,34 +138,29 @@ impl<'a> Scanner<'a> {
         }
     }
 
+    /// The current character, or `\0` at the end of the source.
     fn peek(&self) -> char {
-        self.source[self.current..=self.current]
-            .chars()
-            .next()
-            .unwrap()
+        self.source[self.current..].chars().next().unwrap_or('\0')
     }
 
     fn peek_next(&self) -> char {
-        self.source[self.current + 1..=self.current + 1]
-            .chars()
-            .next()
-            .unwrap()
+        let mut chars = self.source[self.current..].chars();
+        chars.next();
+        chars.next().unwrap_or('\0')
     }
 
     fn follow(&mut self, expected: char) -> bool {
-        if self.is_at_end() {
-            false
-        } else if self.peek() != expected {
+        if self.is_at_end() || self.peek() != expected {
             false
         } else {
-            self.current += 1;
+            self.advance();
             true
         }
     }
 
     fn advance(&mut self) -> char {
         let current = self.peek();
-        self.current += 1;
+        self.current += current.len_utf8();
         current
     }
 
@@ -179,7 +175,7 @@ impl<'a> Scanner<'a> {
     fn error_token(&self, message: &'a str) -> Token<'a> {
         Token {
             ty: TokenType::Error,
-            lexeme: message.as_ref(),
+            lexeme: message,
             line: self.line,
         }
     }
@@ -210,6 +206,7 @@ pub enum TokenType {
     Semicolon,
     Slash,
     Star,
+    Percent,
     // One or two characters tokens
     Bang,
     BangEqual,
@@ -219,6 +216,8 @@ pub enum TokenType {
     GreaterEqual,
     Less,
     LessEqual,
+    StarStar,
+    TildeSlash,
     // Literals
     Identifier,
     String,
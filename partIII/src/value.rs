@@ -1,15 +1,55 @@
-pub type Value = f64;
+use std::{fmt::Display, rc::Rc};
 
-#[derive(Debug, Default, Clone)]
-pub struct ValueArray(Vec<Value>);
+use crate::object::{Obj, ObjFunction, ObjString};
 
-impl ValueArray {
-    pub fn new() -> Self {
-        Self::default()
+/// A value on the VM stack, tagged with its type.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum Value {
+    Bool(bool),
+    #[default]
+    Nil,
+    Number(f64),
+    Obj(Obj),
+}
+
+impl Value {
+    /// `nil` and `false` are falsey, everything else is truthy.
+    pub fn is_falsey(&self) -> bool {
+        matches!(self, Self::Nil | Self::Bool(false))
+    }
+}
+
+impl From<f64> for Value {
+    fn from(number: f64) -> Self {
+        Self::Number(number)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(boolean: bool) -> Self {
+        Self::Bool(boolean)
     }
+}
+
+impl From<ObjString> for Value {
+    fn from(string: ObjString) -> Self {
+        Self::Obj(Obj::String(string))
+    }
+}
+
+impl From<Rc<ObjFunction>> for Value {
+    fn from(function: Rc<ObjFunction>) -> Self {
+        Self::Obj(Obj::Function(function))
+    }
+}
 
-    pub fn add_constant(&mut self, value: Value) -> usize {
-        self.0.push(value);
-        self.0.len() - 1
+impl Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Bool(boolean) => write!(f, "{boolean}"),
+            Self::Nil => write!(f, "nil"),
+            Self::Number(number) => write!(f, "{number}"),
+            Self::Obj(obj) => write!(f, "{obj}"),
+        }
     }
 }
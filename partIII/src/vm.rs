@@ -1,65 +1,320 @@
+use std::{collections::HashMap, rc::Rc};
+
 use crate::{
     chunk::{Chunk, OpCode},
+    native_functions,
+    object::{Heap, Obj, ObjFunction, ObjNative, ObjString},
     value::Value,
 };
 
+/// How deep calls can nest before it's a stack overflow.
+const FRAMES_MAX: usize = 64;
+
+/// A function being run.
+#[derive(Debug, Clone)]
+struct CallFrame {
+    function: Rc<ObjFunction>,
+    ip: usize,
+    /// Where the function's window of the stack starts, its first slot holds
+    /// the function itself and the arguments follow.
+    slots: usize,
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct Vm {
     stack: Vec<Value>,
+    /// The frames of the callers of the running function, which is kept out
+    /// of it while it runs.
+    frames: Vec<CallFrame>,
+    globals: HashMap<ObjString, Value>,
+    heap: Heap,
 }
 
 impl Vm {
     pub fn new() -> Self {
-        Self::default()
+        let mut vm = Self::default();
+        for native in native_functions::NATIVES {
+            vm.define_native(native);
+        }
+        vm
+    }
+
+    fn define_native(&mut self, native: ObjNative) {
+        let name = self.heap.intern(native.name);
+        self.globals
+            .insert(name, Value::Obj(Obj::Native(Rc::new(native))));
     }
 
     pub fn interpret(&mut self, source: &str) -> InterpretResult {
-        let compiled = crate::compiler::Parser::compile(source).unwrap();
-        self.run(&compiled)
+        let function = match crate::compiler::Parser::compile(source, &mut self.heap) {
+            Ok(function) => Rc::new(function),
+            Err(error) => {
+                eprintln!("{error}");
+                return InterpretResult::CompileError;
+            }
+        };
+
+        self.push_value(Rc::clone(&function).into());
+        self.run(CallFrame {
+            function,
+            ip: 0,
+            slots: 0,
+        })
     }
 
     fn push_value(&mut self, value: Value) {
-        println!("pushing {}", value);
+        log::trace!("pushing {}", value);
         self.stack.push(value);
     }
 
     fn pop_value(&mut self) -> Value {
         let value = self.stack.pop().unwrap();
-        println!("poping {}", value);
+        log::trace!("popping {}", value);
         value
     }
 
-    fn binary_op(&mut self, op: impl Fn(Value, Value) -> Value) {
-        let b = self.pop_value();
-        let a = self.pop_value();
+    /// The value `distance` slots down from the top of the stack.
+    fn peek_value(&self, distance: usize) -> &Value {
+        &self.stack[self.stack.len() - 1 - distance]
+    }
+
+    /// Report an error at the instruction before the `ip` of `frame`, with
+    /// the calls that led to it. The stack is lost.
+    fn runtime_error(&mut self, frame: &CallFrame, message: impl AsRef<str>) -> InterpretResult {
+        eprintln!("{}", message.as_ref());
+        for frame in std::iter::once(frame).chain(self.frames.iter().rev()) {
+            let line = frame.function.chunk.lines[frame.ip - 1];
+            match &frame.function.name {
+                Some(name) => eprintln!("[line {line}] in {name}()"),
+                None => eprintln!("[line {line}] in script"),
+            }
+        }
+        self.stack.clear();
+        self.frames.clear();
+        InterpretResult::RuntimeError
+    }
+
+    /// Call the value below the `arg_count` arguments on top of the stack. A
+    /// function gets a new frame, which replaces `frame` until it returns.
+    fn call_value(&mut self, frame: &mut CallFrame, arg_count: usize) -> Result<(), String> {
+        match self.peek_value(arg_count) {
+            Value::Obj(Obj::Function(function)) => {
+                let function = Rc::clone(function);
+                if arg_count != function.arity {
+                    return Err(format!(
+                        "Expected {} arguments but got {arg_count}.",
+                        function.arity
+                    ));
+                }
+                if self.frames.len() + 1 == FRAMES_MAX {
+                    return Err("Stack overflow.".to_string());
+                }
+
+                let callee = CallFrame {
+                    function,
+                    ip: 0,
+                    slots: self.stack.len() - arg_count - 1,
+                };
+                self.frames.push(std::mem::replace(frame, callee));
+                Ok(())
+            }
+            Value::Obj(Obj::Native(native)) => {
+                let native = **native;
+                if arg_count != native.arity {
+                    return Err(format!(
+                        "Expected {} arguments but got {arg_count}.",
+                        native.arity
+                    ));
+                }
+
+                let arguments = self.stack.len() - arg_count;
+                let result = (native.function)(&mut self.heap, &self.stack[arguments..])?;
+                self.stack.truncate(arguments - 1);
+                self.push_value(result);
+                Ok(())
+            }
+            _ => Err("Can only call functions.".to_string()),
+        }
+    }
+
+    /// Apply `op` to the two numbers on top of the stack.
+    fn binary_op(&mut self, op: impl Fn(f64, f64) -> Value) -> Result<(), String> {
+        let (&Value::Number(a), &Value::Number(b)) = (self.peek_value(1), self.peek_value(0))
+        else {
+            return Err("Operands must be numbers.".to_string());
+        };
+        self.pop_value();
+        self.pop_value();
         self.push_value(op(a, b));
+        Ok(())
     }
 
-    fn run(&mut self, chunk: &Chunk) -> InterpretResult {
-        let ip = &mut 0;
+    /// Add two numbers or concatenate two strings.
+    fn add(&mut self) -> Result<(), String> {
+        let (Value::Obj(Obj::String(a)), Value::Obj(Obj::String(b))) =
+            (self.peek_value(1), self.peek_value(0))
+        else {
+            return self
+                .binary_op(|a, b| Value::Number(a + b))
+                .map_err(|_| "Operands must be two numbers or two strings.".to_string());
+        };
+
+        let string = self.heap.intern(&format!("{a}{b}"));
+        self.pop_value();
+        self.pop_value();
+        self.push_value(string.into());
+        Ok(())
+    }
 
+    fn run(&mut self, mut frame: CallFrame) -> InterpretResult {
         loop {
+            let chunk = &frame.function.chunk;
+            let ip = &mut frame.ip;
+            if log::log_enabled!(log::Level::Debug) {
+                chunk.disassemble_instruction(*ip);
+            }
             let opcode = chunk.read_opcode(ip);
-            println!("executing {:?}", opcode);
 
-            match opcode {
+            let result = match opcode {
                 OpCode::Constant => {
                     let constant = chunk.read_constant(ip);
                     self.push_value(constant);
+                    Ok(())
+                }
+                OpCode::Nil => {
+                    self.push_value(Value::Nil);
+                    Ok(())
+                }
+                OpCode::True => {
+                    self.push_value(Value::Bool(true));
+                    Ok(())
+                }
+                OpCode::False => {
+                    self.push_value(Value::Bool(false));
+                    Ok(())
                 }
-                OpCode::Add => self.binary_op(|a, b| a + b),
-                OpCode::Subtract => self.binary_op(|a, b| a - b),
-                OpCode::Multiply => self.binary_op(|a, b| a * b),
-                OpCode::Divide => self.binary_op(|a, b| a / b),
-                OpCode::Negate => {
+                OpCode::Equal => {
+                    let b = self.pop_value();
+                    let a = self.pop_value();
+                    self.push_value(Value::Bool(a == b));
+                    Ok(())
+                }
+                OpCode::Greater => self.binary_op(|a, b| Value::Bool(a > b)),
+                OpCode::Less => self.binary_op(|a, b| Value::Bool(a < b)),
+                OpCode::Add => self.add(),
+                OpCode::Subtract => self.binary_op(|a, b| Value::Number(a - b)),
+                OpCode::Multiply => self.binary_op(|a, b| Value::Number(a * b)),
+                OpCode::Divide => self.binary_op(|a, b| Value::Number(a / b)),
+                // same as partII, rather than a NaN or an infinity, once both
+                // operands are known to be numbers
+                OpCode::Modulo | OpCode::IntegerDivide
+                    if matches!(
+                        (self.peek_value(1), self.peek_value(0)),
+                        (Value::Number(_), Value::Number(b)) if *b == 0.0
+                    ) =>
+                {
+                    Err("Division by zero.".to_string())
+                }
+                OpCode::Modulo => self.binary_op(|a, b| Value::Number(a % b)),
+                // truncate, so that `a == (a ~/ b) * b + a % b`
+                OpCode::IntegerDivide => self.binary_op(|a, b| Value::Number((a / b).trunc())),
+                OpCode::Power => self.binary_op(|a, b| Value::Number(a.powf(b))),
+                OpCode::Not => {
                     let value = self.pop_value();
-                    self.push_value(-value);
+                    self.push_value(Value::Bool(value.is_falsey()));
+                    Ok(())
                 }
-                OpCode::Return => {
+                OpCode::Negate => match *self.peek_value(0) {
+                    Value::Number(number) => {
+                        self.pop_value();
+                        self.push_value(Value::Number(-number));
+                        Ok(())
+                    }
+                    _ => Err("Operand must be a number.".to_string()),
+                },
+                OpCode::Print => {
                     let value = self.pop_value();
-                    println!("ret: {}", value);
-                    return InterpretResult::Ok;
+                    println!("{}", value);
+                    Ok(())
+                }
+                OpCode::Pop => {
+                    self.pop_value();
+                    Ok(())
+                }
+                OpCode::DefineGlobal => {
+                    let name = chunk.read_name(ip);
+                    let value = self.pop_value();
+                    self.globals.insert(name.clone(), value);
+                    Ok(())
+                }
+                OpCode::GetGlobal => {
+                    let name = chunk.read_name(ip);
+                    match self.globals.get(name).cloned() {
+                        Some(value) => {
+                            self.push_value(value);
+                            Ok(())
+                        }
+                        None => Err(format!("Undefined variable `{name}`.")),
+                    }
+                }
+                OpCode::SetGlobal => {
+                    let name = chunk.read_name(ip);
+                    let value = self.peek_value(0).clone();
+                    match self.globals.get_mut(name) {
+                        // an assignment is an expression, its value stays
+                        Some(global) => {
+                            *global = value;
+                            Ok(())
+                        }
+                        None => Err(format!("Undefined variable `{name}`.")),
+                    }
+                }
+                OpCode::GetLocal => {
+                    let slot = chunk.read_byte(ip);
+                    self.push_value(self.stack[frame.slots + slot as usize].clone());
+                    Ok(())
                 }
+                OpCode::SetLocal => {
+                    let slot = chunk.read_byte(ip);
+                    self.stack[frame.slots + slot as usize] = self.peek_value(0).clone();
+                    Ok(())
+                }
+                OpCode::Jump => {
+                    let jump = chunk.read_short(ip);
+                    *ip += jump as usize;
+                    Ok(())
+                }
+                OpCode::JumpIfFalse => {
+                    let jump = chunk.read_short(ip);
+                    if self.peek_value(0).is_falsey() {
+                        *ip += jump as usize;
+                    }
+                    Ok(())
+                }
+                OpCode::Loop => {
+                    let jump = chunk.read_short(ip);
+                    *ip -= jump as usize;
+                    Ok(())
+                }
+                OpCode::Call => {
+                    let arg_count = chunk.read_byte(ip);
+                    self.call_value(&mut frame, arg_count as usize)
+                }
+                OpCode::Return => {
+                    let result = self.pop_value();
+                    // discard the window of the function
+                    self.stack.truncate(frame.slots);
+                    let Some(caller) = self.frames.pop() else {
+                        return InterpretResult::Ok;
+                    };
+                    frame = caller;
+                    self.push_value(result);
+                    Ok(())
+                }
+            };
+
+            if let Err(message) = result {
+                return self.runtime_error(&frame, message);
             }
         }
     }
@@ -72,13 +327,25 @@ impl Chunk {
         byte
     }
 
+    fn read_short(&self, idx: &mut usize) -> u16 {
+        let short = self.read_u16(*idx);
+        *idx += 2;
+        short
+    }
+
     fn read_opcode(&self, idx: &mut usize) -> OpCode {
-        unsafe { std::mem::transmute(self.read_byte(idx)) }
+        // the compiler only writes valid opcodes at instruction boundaries
+        unsafe { std::mem::transmute::<u8, OpCode>(self.read_byte(idx)) }
     }
 
     fn read_constant(&self, idx: &mut usize) -> Value {
         let idx = self.read_byte(idx);
-        self.constants[idx as usize]
+        self.constants[idx as usize].clone()
+    }
+
+    fn read_name(&self, idx: &mut usize) -> &ObjString {
+        let idx = self.read_byte(idx);
+        &self.names[idx as usize]
     }
 }
 
@@ -0,0 +1,47 @@
+//! Run failing scripts and check the runtime error they report.
+
+use std::process::Command;
+
+/// Run `source` saved as `name`, and return the message of its runtime error.
+fn runtime_error(name: &str, source: &str) -> String {
+    let path = format!("{}/{name}", env!("CARGO_TARGET_TMPDIR"));
+    std::fs::write(&path, source).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_partIII"))
+        .arg(&path)
+        .output()
+        .unwrap();
+    assert_eq!(
+        output.status.code(),
+        Some(70),
+        "{name} didn't fail at runtime"
+    );
+
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    stderr.lines().next().unwrap_or_default().to_string()
+}
+
+#[test]
+fn division_by_zero() {
+    assert_eq!(
+        runtime_error("modulo_zero.lox", "print 7 % 0;"),
+        "Division by zero."
+    );
+    assert_eq!(
+        runtime_error("integer_divide_zero.lox", "print 7 ~/ 0;"),
+        "Division by zero."
+    );
+}
+
+#[test]
+fn operand_types_come_first() {
+    // a zero divisor doesn't hide the wrong operand
+    assert_eq!(
+        runtime_error("modulo_string.lox", "print \"x\" % 0;"),
+        "Operands must be numbers."
+    );
+    assert_eq!(
+        runtime_error("integer_divide_nil.lox", "print nil ~/ 0;"),
+        "Operands must be numbers."
+    );
+}
@@ -0,0 +1,80 @@
+//! Compile jumps over more code than their 16-bit operand reaches, and check
+//! where the jumps shown by the disassembler land.
+
+use clox::{
+    chunk::{Chunk, OpCode},
+    compiler::Parser,
+    error::ParserError,
+    object::Heap,
+};
+
+/// `print true;` compiles to 2 bytes, `!true;` to 3.
+fn statements(bytes: usize) -> String {
+    let mut source = "print true;".repeat(bytes / 2);
+    if bytes % 2 == 1 {
+        source.truncate(source.len() - "print true;".len());
+        source.push_str("!true;");
+    }
+    source
+}
+
+fn compile(source: &str) -> Result<Chunk, ParserError> {
+    let mut heap = Heap::new();
+    Parser::compile(source, &mut heap).map(|script| script.chunk)
+}
+
+fn compile_error(source: &str) -> String {
+    match compile(source) {
+        Ok(_) => panic!("compiled a jump out of reach"),
+        Err(ParserError::At { message, .. }) => message,
+    }
+}
+
+#[test]
+fn oversized_if() {
+    let source = format!("if (true) {{ {} }}", statements(u16::MAX as usize));
+    assert_eq!(compile_error(&source), "Too much code to jump over.");
+}
+
+#[test]
+fn oversized_while() {
+    let source = format!("while (false) {{ {} }}", statements(u16::MAX as usize));
+    assert_eq!(compile_error(&source), "Loop body too large.");
+}
+
+#[test]
+fn longest_if() {
+    // the jump over the then branch also skips its `Pop` and the `Jump` over
+    // the else branch
+    let body = u16::MAX as usize - 4;
+    let chunk = compile(&format!("if (true) {{ {} }}", statements(body))).unwrap();
+    assert_eq!(chunk.code[1], OpCode::JumpIfFalse as u8);
+    assert_eq!(chunk.read_u16(2), u16::MAX);
+    assert_eq!(chunk.jump_target(1), 4 + 1 + body + 3);
+
+    let source = format!("if (true) {{ {} }}", statements(body + 1));
+    assert_eq!(compile_error(&source), "Too much code to jump over.");
+}
+
+#[test]
+fn jump_targets() {
+    let chunk = compile(
+        "while (false) print true;
+         if (true) print 1; else print 2;",
+    )
+    .unwrap();
+
+    // 0 False, 1 JumpIfFalse, 4 Pop, 5 True, 6 Print, 7 Loop, 10 Pop,
+    // 11 True, 12 JumpIfFalse, 15 Pop, 16 Constant, 18 Print, 19 Jump,
+    // 22 Pop, 23 Constant, 25 Print, 26 Nil, 27 Return
+    for (offset, opcode, target) in [
+        (1, OpCode::JumpIfFalse, 10),
+        (7, OpCode::Loop, 0),
+        (12, OpCode::JumpIfFalse, 22),
+        (19, OpCode::Jump, 26),
+    ] {
+        assert_eq!(chunk.code[offset], opcode as u8, "at {offset}");
+        assert_eq!(chunk.jump_target(offset), target, "{opcode:?} at {offset}");
+    }
+    assert_eq!(chunk.code[26], OpCode::Nil as u8);
+}
@@ -0,0 +1,116 @@
+//! Run code samples and compare what they print with their `// expect: `
+//! comments.
+
+use std::process::Command;
+
+/// The lines a sample should print, in order.
+fn expected(source: &str) -> Vec<&str> {
+    source
+        .lines()
+        .filter_map(|line| line.split_once("// expect: ").map(|(_, expect)| expect))
+        .collect()
+}
+
+fn run_sample(name: &str) {
+    run(&format!(
+        "{}/code_samples/{name}",
+        env!("CARGO_MANIFEST_DIR")
+    ));
+}
+
+fn run(path: &str) {
+    let source = std::fs::read_to_string(path).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_partIII"))
+        .arg(path)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{path} failed:\n{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(
+        stdout.lines().collect::<Vec<_>>(),
+        expected(&source),
+        "{path}"
+    );
+}
+
+#[test]
+fn arithmetic() {
+    run_sample("arithmetic.lox");
+}
+
+#[test]
+fn values() {
+    run_sample("values.lox");
+}
+
+#[test]
+fn strings() {
+    run_sample("strings.lox");
+}
+
+#[test]
+fn variables() {
+    run_sample("variables.lox");
+}
+
+#[test]
+fn control_flow() {
+    run_sample("control_flow.lox");
+}
+
+#[test]
+fn functions() {
+    run_sample("functions.lox");
+}
+
+/// The samples of the tree-walking interpreter that only use what the VM
+/// supports.
+mod partii {
+    fn run_sample(name: &str) {
+        super::run(&format!(
+            "{}/../partII/code_samples/{name}",
+            env!("CARGO_MANIFEST_DIR")
+        ));
+    }
+
+    #[test]
+    fn simple() {
+        run_sample("simple.lox");
+    }
+
+    #[test]
+    fn scope() {
+        run_sample("scope.lox");
+    }
+
+    #[test]
+    fn function() {
+        run_sample("function.lox");
+    }
+
+    #[test]
+    fn recursive_function() {
+        run_sample("recursive_function.lox");
+    }
+
+    #[test]
+    fn bug() {
+        run_sample("bug.lox");
+    }
+
+    #[test]
+    fn arithmetic() {
+        run_sample("arithmetic.lox");
+    }
+
+    #[test]
+    fn equality() {
+        run_sample("equality.lox");
+    }
+}